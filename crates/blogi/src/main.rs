@@ -1,6 +1,7 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
 
 use anyhow::Result;
+use blogi_api::ApiConfig;
 use blogi_db::{pg::PostgresDatastore, Datastore};
 use clap::{Parser, Subcommand};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
@@ -16,16 +17,66 @@ struct Cli {
     database_url: String,
 }
 
+// Parsed once at startup, so the API's many options aren't worth boxing.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Command {
     /// Start the API server
     Api {
         #[arg(long, short, default_value = "0.0.0.0:8000")]
         bind_addr: SocketAddr,
+
+        /// The public URL readers reach the API at
+        #[arg(long, env = "API_PUBLIC_URL")]
+        public_url: String,
+
+        /// Where to cache blobs fetched from PDSes
+        #[arg(long, env = "BLOB_CACHE_DIR", default_value = "blobs")]
+        blob_cache_dir: PathBuf,
+
+        /// The maximum size of the blob cache, in bytes
+        #[arg(long, env = "BLOB_CACHE_MAX_SIZE", default_value_t = 1024 * 1024 * 1024)]
+        blob_cache_max_size: u64,
+
+        /// The Bluesky AppView to read replies to announcement posts from
+        #[arg(long, env = "BSKY_APPVIEW_URL", default_value = "https://public.api.bsky.app")]
+        bsky_appview_url: String,
+
+        /// This service's DID, which authenticated requests must be addressed to
+        #[arg(long, env = "ATPROTO_DID")]
+        service_did: String,
+
+        /// The hex-encoded secp256k1 key to publish in this service's DID document
+        #[arg(long, env = "ATPROTO_SIGNING_KEY", hide_env_values = true)]
+        signing_key: Option<String>,
+
+        /// The PLC directory to resolve did:plc identities through
+        #[arg(long, env = "PLC_DIRECTORY", default_value = "https://plc.directory")]
+        plc_directory_url: String,
+
+        /// The key sign-in sessions are encrypted with
+        #[arg(long, env = "SECRET_KEY", hide_env_values = true)]
+        secret_key: String,
+
+        /// The Redis instance to keep sign-in sessions in
+        #[arg(long, env = "REDIS_URL", default_value = "redis://localhost:6379/0")]
+        redis_url: String,
     },
 
     /// Start the ingester
-    Ingester {},
+    Ingester {
+        /// The Jetstream instance to read commits from
+        #[arg(long, env = "JETSTREAM_URL", default_value = "wss://jetstream2.us-east.bsky.network/subscribe")]
+        jetstream_url: String,
+
+        /// The PLC directory to resolve did:plc identities through
+        #[arg(long, env = "PLC_DIRECTORY", default_value = "https://plc.directory")]
+        plc_directory_url: String,
+
+        /// The AppView to resolve handles through when they aren't published over HTTPS
+        #[arg(long, env = "BSKY_APPVIEW_URL", default_value = "https://public.api.bsky.app")]
+        bsky_appview_url: String,
+    },
 }
 
 #[tokio::main]
@@ -42,12 +93,35 @@ async fn main() -> Result<()> {
     let db = PostgresDatastore::open(&cli.database_url).await?;
 
     match cli.command {
-        Command::Api { bind_addr } => {
-            blogi_api::start(bind_addr, db.boxed()).await
+        Command::Api {
+            bind_addr,
+            public_url,
+            blob_cache_dir,
+            blob_cache_max_size,
+            bsky_appview_url,
+            service_did,
+            signing_key,
+            plc_directory_url,
+            secret_key,
+            redis_url,
+        } => {
+            let config = ApiConfig {
+                blob_cache_dir,
+                blob_cache_max_size,
+                bsky_appview_url,
+                service_did,
+                signing_key,
+                plc_directory_url,
+                secret_key,
+                redis_url,
+                ..ApiConfig::new(&public_url)
+            };
+
+            blogi_api::start(bind_addr, config, db.boxed()).await
         },
 
-        Command::Ingester {} => {
-            blogi_ingester::start(db.boxed()).await
+        Command::Ingester { jetstream_url, plc_directory_url, bsky_appview_url } => {
+            blogi_ingester::start(jetstream_url, &plc_directory_url, &bsky_appview_url, db.boxed()).await
         },
     }
 }
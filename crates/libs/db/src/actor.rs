@@ -1,11 +1,172 @@
 use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as};
 
 use crate::pg::PostgresDatastore;
 
+pub struct Actor {
+    pub did: String,
+    pub handle: String,
+    pub pds_endpoint: Option<String>,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub avatar_cid: Option<String>,
+    pub banner_cid: Option<String>,
+    pub posts_count: i64,
+    pub created_at: Option<DateTime<Utc>>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+/// What an account's `moe.hayden.blogi.actor.profile` record says about it.
+pub struct Profile {
+    pub did: String,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub avatar_cid: Option<String>,
+    pub banner_cid: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// The handle that stands in for one that doesn't resolve back to its DID.
+pub const INVALID_HANDLE: &str = "handle.invalid";
+
 #[async_trait]
 pub trait ActorRepository {
+    /// Looks up an actor by either their DID or their handle.
+    async fn get_actor(&self, identifier: &str) -> Result<Option<Actor>>;
+
+    async fn get_actors(&self, dids: &[String]) -> Result<Vec<Actor>>;
+
+    /// Records an account's verified handle and its PDS, creating the actor
+    /// if it's new. A handle can only belong to one account, so any other
+    /// actor still holding it loses it.
+    async fn put_actor_identity(&self, did: &str, handle: &str, pds_endpoint: &str) -> Success;
+
+    /// Indexes an account's profile, creating the actor if it's new.
+    async fn put_profile(&self, profile: &Profile) -> Success;
+
+    /// Clears an account's profile, keeping its identity.
+    async fn delete_profile(&self, did: &str) -> Success;
 }
 
+#[async_trait]
 impl ActorRepository for PostgresDatastore {
+    async fn get_actor(&self, identifier: &str) -> Result<Option<Actor>> {
+        let actor = query_as!(
+            Actor,
+            r#"
+            SELECT
+                did, handle, pds_endpoint, display_name, description,
+                avatar_cid, banner_cid, created_at, indexed_at,
+                (SELECT count(*) FROM entry WHERE entry.did = actor.did AND entry_listed(entry, NULL)) AS "posts_count!"
+            FROM actor
+            WHERE did = $1 OR handle = $1
+            "#,
+            identifier,
+        )
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(actor)
+    }
+
+    async fn get_actors(&self, dids: &[String]) -> Result<Vec<Actor>> {
+        let actors = query_as!(
+            Actor,
+            r#"
+            SELECT
+                did, handle, pds_endpoint, display_name, description,
+                avatar_cid, banner_cid, created_at, indexed_at,
+                (SELECT count(*) FROM entry WHERE entry.did = actor.did AND entry_listed(entry, NULL)) AS "posts_count!"
+            FROM actor
+            WHERE did = ANY($1)
+            "#,
+            dids,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(actors)
+    }
+
+    async fn put_actor_identity(&self, did: &str, handle: &str, pds_endpoint: &str) -> Success {
+        let mut tx = self.0.begin().await?;
+
+        if handle != INVALID_HANDLE {
+            query!(
+                "UPDATE actor SET handle = $3, indexed_at = now() WHERE handle = $1 AND did <> $2",
+                handle,
+                did,
+                INVALID_HANDLE,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        query!(
+            r#"
+            INSERT INTO actor (did, handle, pds_endpoint)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (did) DO UPDATE SET
+                handle = excluded.handle,
+                pds_endpoint = excluded.pds_endpoint,
+                indexed_at = now()
+            "#,
+            did,
+            handle,
+            pds_endpoint,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn put_profile(&self, profile: &Profile) -> Success {
+        query!(
+            r#"
+            INSERT INTO actor (did, handle, display_name, description, avatar_cid, banner_cid, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (did) DO UPDATE SET
+                display_name = excluded.display_name,
+                description = excluded.description,
+                avatar_cid = excluded.avatar_cid,
+                banner_cid = excluded.banner_cid,
+                created_at = excluded.created_at,
+                indexed_at = now()
+            "#,
+            profile.did,
+            INVALID_HANDLE,
+            profile.display_name,
+            profile.description,
+            profile.avatar_cid,
+            profile.banner_cid,
+            profile.created_at,
+        )
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_profile(&self, did: &str) -> Success {
+        query!(
+            r#"
+            UPDATE actor SET
+                display_name = NULL,
+                description = NULL,
+                avatar_cid = NULL,
+                banner_cid = NULL,
+                created_at = NULL,
+                indexed_at = now()
+            WHERE did = $1
+            "#,
+            did,
+        )
+        .execute(&self.0)
+        .await?;
 
+        Ok(())
+    }
 }
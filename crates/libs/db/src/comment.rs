@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as};
+
+use crate::{pagination::Cursor, pg::PostgresDatastore};
+
+pub struct Comment {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub rkey: String,
+    pub post_uri: String,
+    pub post_cid: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait CommentRepository {
+    /// Lists comments on a post, oldest first.
+    async fn list_comments(&self, post_uri: &str, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Comment>>;
+
+    /// Indexes a comment, replacing any previous version of it.
+    async fn put_comment(&self, comment: &Comment) -> Success;
+
+    async fn delete_comment(&self, uri: &str) -> Success;
+}
+
+#[async_trait]
+impl CommentRepository for PostgresDatastore {
+    async fn list_comments(&self, post_uri: &str, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Comment>> {
+        let (after, after_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let comments = query_as!(
+            Comment,
+            r#"
+            SELECT uri, cid, did, rkey, post_uri, post_cid, content, created_at, updated_at, indexed_at
+            FROM comment
+            WHERE post_uri = $1
+                AND ($2::TIMESTAMPTZ IS NULL OR (created_at, uri) > ($2, $4::TEXT))
+            ORDER BY created_at, uri
+            LIMIT $3
+            "#,
+            post_uri,
+            after,
+            limit,
+            after_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(comments)
+    }
+
+    async fn put_comment(&self, comment: &Comment) -> Success {
+        query!(
+            r#"
+            INSERT INTO comment (uri, cid, did, rkey, post_uri, post_cid, content, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (uri) DO UPDATE SET
+                cid = excluded.cid,
+                post_uri = excluded.post_uri,
+                post_cid = excluded.post_cid,
+                content = excluded.content,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                indexed_at = now()
+            "#,
+            comment.uri,
+            comment.cid,
+            comment.did,
+            comment.rkey,
+            comment.post_uri,
+            comment.post_cid,
+            comment.content,
+            comment.created_at,
+            comment.updated_at,
+        )
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_comment(&self, uri: &str) -> Success {
+        query!("DELETE FROM comment WHERE uri = $1", uri)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+}
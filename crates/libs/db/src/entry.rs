@@ -0,0 +1,567 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{query, query_as, types::Json};
+
+use crate::{pagination::Cursor, pg::PostgresDatastore};
+
+pub struct Entry {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub rkey: String,
+    pub title: String,
+    pub content: String,
+    pub content_format: Option<String>,
+    pub status: Option<String>,
+    pub visibility: Option<String>,
+    pub tags: Vec<String>,
+    /// Languages the entry is written in, main one first.
+    pub langs: Vec<String>,
+    /// The Bluesky post announcing the entry, if any.
+    pub bsky_post_uri: Option<String>,
+    pub bsky_post_cid: Option<String>,
+    /// When the entry goes live, if it was scheduled.
+    pub publish_at: Option<DateTime<Utc>>,
+    pub images: Json<Vec<EntryImage>>,
+    pub comment_count: i64,
+    /// Distinct accounts that have liked the entry. Duplicate like records
+    /// only count once.
+    pub like_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+impl Entry {
+    /// When the entry went, or goes, live. Feeds are ordered by this.
+    pub fn published_at(&self) -> DateTime<Utc> {
+        self.publish_at.unwrap_or(self.created_at)
+    }
+}
+
+/// A blob reference from an entry's `images` array.
+#[derive(Debug, Deserialize)]
+pub struct EntryImage {
+    pub cid: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub alt: String,
+    pub aspect_width: Option<i64>,
+    pub aspect_height: Option<i64>,
+}
+
+/// A version of an entry that has since been replaced.
+pub struct EntryRevision {
+    pub entry_uri: String,
+    pub cid: String,
+    pub title: String,
+    pub content: String,
+    pub content_format: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub indexed_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// An entry matching a search query.
+pub struct SearchResult {
+    pub entry: Entry,
+    pub rank: f32,
+    /// Excerpt of the content around the matches, which are wrapped in
+    /// [`MATCH_START`] and [`MATCH_END`].
+    pub snippet: String,
+}
+
+/// Control characters marking matches in a [`SearchResult`] snippet, so
+/// callers can escape the text before highlighting them.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// Tags are matched case-insensitively, so they're stored lowercased.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Reads and indexes blog entries.
+///
+/// Reads take the DID of the requesting account, if any, as `viewer`. Who
+/// can see what is decided by the `entry_visible` and `entry_listed` SQL
+/// functions: listings only include public, live, published entries, single
+/// entries may also be unlisted, and authors always see their own.
+#[async_trait]
+pub trait EntryRepository {
+    async fn get_entry(&self, did: &str, rkey: &str, viewer: Option<&str>) -> Result<Option<Entry>>;
+
+    /// Lists entries with a tag, newest first, optionally only those by one author.
+    async fn list_entries_by_tag(
+        &self,
+        tag: &str,
+        did: Option<&str>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>>;
+
+    /// Lists one author's entries, newest first.
+    async fn list_entries_for_author(
+        &self,
+        did: &str,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>>;
+
+    /// Lists entries by every author, newest first.
+    async fn list_timeline(&self, viewer: Option<&str>, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Entry>>;
+
+    /// Lists entries published since `since`, most commented first.
+    async fn list_popular_entries(
+        &self,
+        since: DateTime<Utc>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>>;
+
+    /// Searches entries' titles and content, best matches first, optionally
+    /// only those by one author. `query` uses web search syntax.
+    async fn search_entries(
+        &self,
+        query: &str,
+        did: Option<&str>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Lists the versions an entry has had before its current one that
+    /// `viewer` can see, newest first.
+    async fn list_entry_revisions(&self, uri: &str, viewer: Option<&str>) -> Result<Vec<EntryRevision>>;
+
+    /// Indexes an entry. A previous version with a different CID is kept as
+    /// a revision.
+    async fn put_entry(&self, entry: &Entry) -> Success;
+
+    /// Removes an entry, along with its images and revisions.
+    async fn delete_entry(&self, uri: &str) -> Success;
+
+    /// Marks scheduled entries whose publish time has passed as announced
+    /// and reindexed, as if they had just come in live, and returns the URIs
+    /// of those that are listed publicly. Each entry is only announced once,
+    /// however many schedulers are running.
+    async fn announce_published_entries(&self) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl EntryRepository for PostgresDatastore {
+    async fn get_entry(&self, did: &str, rkey: &str, viewer: Option<&str>) -> Result<Option<Entry>> {
+        let entry = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM entry
+            WHERE did = $1 AND rkey = $2 AND entry_visible(entry, $3)
+            "#,
+            did,
+            rkey,
+            viewer,
+        )
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(entry)
+    }
+
+    async fn list_entries_by_tag(
+        &self,
+        tag: &str,
+        did: Option<&str>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>> {
+        let (before, before_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM entry
+            WHERE tags @> ARRAY[$1]
+                AND ($2::TEXT IS NULL OR did = $2)
+                AND entry_listed(entry, $5)
+                AND ($3::TIMESTAMPTZ IS NULL OR (published_at, uri) < ($3, $6::TEXT))
+            ORDER BY published_at DESC, uri DESC
+            LIMIT $4
+            "#,
+            normalize_tag(tag),
+            did,
+            before,
+            limit,
+            viewer,
+            before_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn list_entries_for_author(
+        &self,
+        did: &str,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>> {
+        let (before, before_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM entry
+            WHERE did = $1
+                AND entry_listed(entry, $4)
+                AND ($2::TIMESTAMPTZ IS NULL OR (published_at, uri) < ($2, $5::TEXT))
+            ORDER BY published_at DESC, uri DESC
+            LIMIT $3
+            "#,
+            did,
+            before,
+            limit,
+            viewer,
+            before_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn list_timeline(&self, viewer: Option<&str>, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Entry>> {
+        let (before, before_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM entry
+            WHERE entry_listed(entry, $3)
+                AND ($1::TIMESTAMPTZ IS NULL OR (published_at, uri) < ($1, $4::TEXT))
+            ORDER BY published_at DESC, uri DESC
+            LIMIT $2
+            "#,
+            before,
+            limit,
+            viewer,
+            before_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn list_popular_entries(
+        &self,
+        since: DateTime<Utc>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>> {
+        let (after_count, after_uri) = cursor.map(Cursor::count).transpose()?.unzip();
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(ranked.uri) AS "images!: Json<Vec<EntryImage>>",
+                comment_count AS "comment_count!",
+                entry_like_count(ranked.uri) AS "like_count!"
+            FROM (
+                SELECT entry.*, entry_comment_count(entry.uri) AS comment_count
+                FROM entry
+                WHERE entry_listed(entry, $5)
+                    AND published_at >= $1
+            ) ranked
+            WHERE $2::BIGINT IS NULL OR (comment_count, uri) < ($2, $3::TEXT)
+            ORDER BY comment_count DESC, uri DESC
+            LIMIT $4
+            "#,
+            since,
+            after_count,
+            after_uri,
+            limit,
+            viewer,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn search_entries(
+        &self,
+        query: &str,
+        did: Option<&str>,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<SearchResult>> {
+        let (after_rank, after_uri) = cursor.map(Cursor::rank).transpose()?.unzip();
+
+        // The query is parsed with each entry's own search config, so it's
+        // stemmed the same way the entry was. Headlines are only worth
+        // building for the page we return, so rank and limit first.
+        let rows = query!(
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(ranked.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(ranked.uri) AS "comment_count!",
+                entry_like_count(ranked.uri) AS "like_count!",
+                rank AS "rank!",
+                ts_headline(
+                    search_config, content, query,
+                    'StartSel=' || chr(2) || ', StopSel=' || chr(3)
+                        || ', MaxFragments=2, MaxWords=30, MinWords=10, FragmentDelimiter=" … "'
+                ) AS "snippet!"
+            FROM (
+                SELECT entry.*, query, ts_rank_cd(search, query) AS rank
+                FROM entry, websearch_to_tsquery(search_config, $1) query
+                WHERE search @@ query
+                    AND ($2::TEXT IS NULL OR did = $2)
+                    AND entry_listed(entry, $6)
+                    AND ($3::REAL IS NULL OR (ts_rank_cd(search, query), uri) < ($3, $4::TEXT))
+                ORDER BY rank DESC, uri DESC
+                LIMIT $5
+            ) ranked
+            ORDER BY rank DESC, uri DESC
+            "#,
+            query,
+            did,
+            after_rank,
+            after_uri,
+            limit,
+            viewer,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SearchResult {
+                entry: Entry {
+                    uri: row.uri,
+                    cid: row.cid,
+                    did: row.did,
+                    rkey: row.rkey,
+                    title: row.title,
+                    content: row.content,
+                    content_format: row.content_format,
+                    status: row.status,
+                    visibility: row.visibility,
+                    tags: row.tags,
+                    langs: row.langs,
+                    bsky_post_uri: row.bsky_post_uri,
+                    bsky_post_cid: row.bsky_post_cid,
+                    publish_at: row.publish_at,
+                    images: row.images,
+                    comment_count: row.comment_count,
+                    like_count: row.like_count,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    indexed_at: row.indexed_at,
+                },
+                rank: row.rank,
+                snippet: row.snippet,
+            })
+            .collect())
+    }
+
+    async fn list_entry_revisions(&self, uri: &str, viewer: Option<&str>) -> Result<Vec<EntryRevision>> {
+        let revisions = query_as!(
+            EntryRevision,
+            r#"
+            SELECT r.entry_uri, r.cid, r.title, r.content, r.content_format, r.updated_at, r.indexed_at, r.replaced_at
+            FROM entry_revision r
+            JOIN entry e ON e.uri = r.entry_uri
+            WHERE r.entry_uri = $1 AND entry_revision_visible(r, e, $2)
+            ORDER BY r.replaced_at DESC
+            "#,
+            uri,
+            viewer,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    async fn put_entry(&self, entry: &Entry) -> Success {
+        let mut tags: Vec<String> = entry.tags.iter().map(|tag| normalize_tag(tag)).collect();
+        tags.retain(|tag| !tag.is_empty());
+        tags.sort();
+        tags.dedup();
+
+        let mut tx = self.0.begin().await?;
+
+        query!(
+            r#"
+            INSERT INTO entry_revision (
+                entry_uri, cid, title, content, content_format, status, publish_at, updated_at, indexed_at
+            )
+            SELECT uri, cid, title, content, content_format, status, publish_at, updated_at, indexed_at
+            FROM entry
+            WHERE uri = $1 AND cid <> $2
+            ON CONFLICT DO NOTHING
+            "#,
+            entry.uri,
+            entry.cid,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!(
+            r#"
+            INSERT INTO entry (
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            ON CONFLICT (uri) DO UPDATE SET
+                cid = excluded.cid,
+                title = excluded.title,
+                content = excluded.content,
+                content_format = excluded.content_format,
+                status = excluded.status,
+                visibility = excluded.visibility,
+                tags = excluded.tags,
+                langs = excluded.langs,
+                bsky_post_uri = excluded.bsky_post_uri,
+                bsky_post_cid = excluded.bsky_post_cid,
+                publish_at = excluded.publish_at,
+                -- Rescheduling or listing an entry announces it again when it
+                -- next goes live.
+                announced_at = CASE
+                    WHEN excluded.publish_at IS DISTINCT FROM entry.publish_at
+                        OR excluded.visibility IS DISTINCT FROM entry.visibility THEN NULL
+                    ELSE entry.announced_at
+                END,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                indexed_at = now()
+            "#,
+            entry.uri,
+            entry.cid,
+            entry.did,
+            entry.rkey,
+            entry.title,
+            entry.content,
+            entry.content_format,
+            entry.status,
+            entry.visibility,
+            &tags,
+            &entry.langs,
+            entry.bsky_post_uri,
+            entry.bsky_post_cid,
+            entry.publish_at,
+            entry.created_at,
+            entry.updated_at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!("DELETE FROM entry_image WHERE entry_uri = $1", entry.uri)
+            .execute(&mut *tx)
+            .await?;
+
+        for (position, image) in entry.images.iter().enumerate() {
+            query!(
+                r#"
+                INSERT INTO entry_image (entry_uri, position, cid, mime_type, size, alt, aspect_width, aspect_height)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                entry.uri,
+                position as i32,
+                image.cid,
+                image.mime_type,
+                image.size,
+                image.alt,
+                image.aspect_width,
+                image.aspect_height,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_entry(&self, uri: &str) -> Success {
+        query!("DELETE FROM entry WHERE uri = $1", uri)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn announce_published_entries(&self) -> Result<Vec<String>> {
+        // Unlisted entries are marked too, so they aren't checked again on
+        // every run, but only an entry that goes public is announced. Going
+        // live stamps `indexed_at` the way indexing a live entry does, so
+        // readers polling for new entries by it see the entry as new.
+        let uris = query!(
+            r#"
+            WITH live AS (
+                UPDATE entry SET announced_at = now(), indexed_at = now()
+                WHERE publish_at <= now()
+                    AND announced_at IS NULL
+                    AND coalesce(status, 'live') = 'live'
+                RETURNING entry.*
+            )
+            SELECT live.uri FROM live
+            WHERE entry_listed(live::entry, NULL)
+            "#,
+        )
+        .fetch_all(&self.0)
+        .await?
+        .into_iter()
+        .map(|row| row.uri)
+        .collect();
+
+        Ok(uris)
+    }
+}
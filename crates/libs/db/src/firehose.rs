@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use sqlx::query;
+
+use crate::pg::PostgresDatastore;
+
+#[async_trait]
+pub trait FirehoseRepository {
+    /// The position of the last event indexed from `source`, if any.
+    async fn get_firehose_cursor(&self, source: &str) -> Result<Option<i64>>;
+
+    async fn put_firehose_cursor(&self, source: &str, cursor: i64) -> Success;
+}
+
+#[async_trait]
+impl FirehoseRepository for PostgresDatastore {
+    async fn get_firehose_cursor(&self, source: &str) -> Result<Option<i64>> {
+        let cursor = query!("SELECT cursor FROM firehose_cursor WHERE source = $1", source)
+            .fetch_optional(&self.0)
+            .await?
+            .map(|row| row.cursor);
+
+        Ok(cursor)
+    }
+
+    async fn put_firehose_cursor(&self, source: &str, cursor: i64) -> Success {
+        query!(
+            r#"
+            INSERT INTO firehose_cursor (source, cursor)
+            VALUES ($1, $2)
+            ON CONFLICT (source) DO UPDATE SET
+                cursor = excluded.cursor,
+                updated_at = now()
+            "#,
+            source,
+            cursor,
+        )
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+}
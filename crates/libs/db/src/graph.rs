@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as, types::Json};
+
+use crate::{
+    entry::{Entry, EntryImage},
+    pagination::Cursor,
+    pg::PostgresDatastore,
+};
+
+pub struct Follow {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub rkey: String,
+    pub subject: String,
+    pub created_at: DateTime<Utc>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+/// Distinct accounts following and followed by an actor. Duplicate follow
+/// records only count once.
+pub struct FollowCounts {
+    pub followers: i64,
+    pub follows: i64,
+}
+
+#[async_trait]
+pub trait GraphRepository {
+    async fn get_follow_counts(&self, did: &str) -> Result<FollowCounts>;
+
+    /// Lists entries by the authors `did` follows, newest first, as `did`
+    /// sees them.
+    async fn list_following_entries(
+        &self,
+        did: &str,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>>;
+
+    /// Indexes a follow, replacing any previous version of it.
+    async fn put_follow(&self, follow: &Follow) -> Success;
+
+    async fn delete_follow(&self, uri: &str) -> Success;
+}
+
+#[async_trait]
+impl GraphRepository for PostgresDatastore {
+    async fn get_follow_counts(&self, did: &str) -> Result<FollowCounts> {
+        let counts = query_as!(
+            FollowCounts,
+            r#"
+            SELECT
+                (SELECT count(DISTINCT did) FROM follow WHERE subject = $1) AS "followers!",
+                (SELECT count(DISTINCT subject) FROM follow WHERE did = $1) AS "follows!"
+            "#,
+            did,
+        )
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok(counts)
+    }
+
+    async fn list_following_entries(
+        &self,
+        did: &str,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>> {
+        let (before, before_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, content, content_format, status, visibility, tags, langs,
+                bsky_post_uri, bsky_post_cid, publish_at,
+                created_at, updated_at, indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM entry
+            WHERE did IN (SELECT subject FROM follow WHERE follow.did = $1)
+                AND entry_listed(entry, $1)
+                AND ($2::TIMESTAMPTZ IS NULL OR (published_at, uri) < ($2, $4::TEXT))
+            ORDER BY published_at DESC, uri DESC
+            LIMIT $3
+            "#,
+            did,
+            before,
+            limit,
+            before_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn put_follow(&self, follow: &Follow) -> Success {
+        query!(
+            r#"
+            INSERT INTO follow (uri, cid, did, rkey, subject, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (uri) DO UPDATE SET
+                cid = excluded.cid,
+                subject = excluded.subject,
+                created_at = excluded.created_at,
+                indexed_at = now()
+            "#,
+            follow.uri,
+            follow.cid,
+            follow.did,
+            follow.rkey,
+            follow.subject,
+            follow.created_at,
+        )
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_follow(&self, uri: &str) -> Success {
+        query!("DELETE FROM follow WHERE uri = $1", uri)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -5,12 +5,27 @@ use sqlx::query;
 // Drivers
 pub mod pg;
 
+pub mod pagination;
+pub mod records;
+
 // Repositories
 pub mod actor;
+pub mod comment;
+pub mod entry;
+pub mod firehose;
+pub mod graph;
+pub mod like;
+pub mod series;
 
 #[async_trait]
 pub trait Datastore:
     actor::ActorRepository
+    + comment::CommentRepository
+    + entry::EntryRepository
+    + firehose::FirehoseRepository
+    + graph::GraphRepository
+    + like::LikeRepository
+    + series::SeriesRepository
     + Sync
     + Send
 {
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use sqlx::{query, query_as};
+
+use crate::{pagination::Cursor, pg::PostgresDatastore};
+
+pub struct Like {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub rkey: String,
+    pub subject_uri: String,
+    pub subject_cid: String,
+    pub created_at: DateTime<Utc>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait LikeRepository {
+    /// Lists likes of a record, most recent first, keeping only the latest
+    /// like from each account so the list agrees with the like count.
+    async fn list_likes(&self, subject_uri: &str, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Like>>;
+
+    /// Finds which of `subject_uris` an account has liked, as
+    /// `(subject_uri, like_uri)` pairs.
+    async fn list_viewer_likes(&self, did: &str, subject_uris: &[String]) -> Result<Vec<(String, String)>>;
+
+    /// Indexes a like, replacing any previous version of it.
+    async fn put_like(&self, like: &Like) -> Success;
+
+    async fn delete_like(&self, uri: &str) -> Success;
+}
+
+#[async_trait]
+impl LikeRepository for PostgresDatastore {
+    async fn list_likes(&self, subject_uri: &str, limit: i64, cursor: Option<&Cursor>) -> Result<Vec<Like>> {
+        let (before, before_uri) = cursor.map(Cursor::timestamp).transpose()?.unzip();
+
+        let likes = query_as!(
+            Like,
+            r#"
+            SELECT uri AS "uri!", cid AS "cid!", did AS "did!", rkey AS "rkey!",
+                subject_uri AS "subject_uri!", subject_cid AS "subject_cid!",
+                created_at AS "created_at!", indexed_at AS "indexed_at!"
+            FROM (
+                SELECT DISTINCT ON (did) uri, cid, did, rkey, subject_uri, subject_cid, created_at, indexed_at
+                FROM entry_like
+                WHERE subject_uri = $1
+                ORDER BY did, created_at DESC, uri DESC
+            ) latest
+            WHERE $2::TIMESTAMPTZ IS NULL OR (created_at, uri) < ($2, $4::TEXT)
+            ORDER BY created_at DESC, uri DESC
+            LIMIT $3
+            "#,
+            subject_uri,
+            before,
+            limit,
+            before_uri,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(likes)
+    }
+
+    async fn list_viewer_likes(&self, did: &str, subject_uris: &[String]) -> Result<Vec<(String, String)>> {
+        let likes = query!(
+            r#"
+            SELECT DISTINCT ON (subject_uri) subject_uri, uri
+            FROM entry_like
+            WHERE did = $1 AND subject_uri = ANY($2)
+            ORDER BY subject_uri, created_at
+            "#,
+            did,
+            subject_uris,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(likes.into_iter().map(|like| (like.subject_uri, like.uri)).collect())
+    }
+
+    async fn put_like(&self, like: &Like) -> Success {
+        query!(
+            r#"
+            INSERT INTO entry_like (uri, cid, did, rkey, subject_uri, subject_cid, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (uri) DO UPDATE SET
+                cid = excluded.cid,
+                subject_uri = excluded.subject_uri,
+                subject_cid = excluded.subject_cid,
+                created_at = excluded.created_at,
+                indexed_at = now()
+            "#,
+            like.uri,
+            like.cid,
+            like.did,
+            like.rkey,
+            like.subject_uri,
+            like.subject_cid,
+            like.created_at,
+        )
+        .execute(&self.0)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_like(&self, uri: &str) -> Success {
+        query!("DELETE FROM entry_like WHERE uri = $1", uri)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+}
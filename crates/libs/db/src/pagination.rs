@@ -1,7 +1,163 @@
-pub struct Cursor(pub String);
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use blogi_errors::{BlogiError, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Where a page of a list left off: the sort key of the last item on it,
+/// and that item's URI to break ties between items with the same key.
+///
+/// Clients only ever see cursors as opaque tokens signed with the service's
+/// secret, so they can't craft one to start a list anywhere they like.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub key: SortKey,
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKey {
+    Timestamp(DateTime<Utc>),
+    Count(i64),
+    Rank(f32),
+}
 
 impl Cursor {
-    pub fn new(token: String) -> Cursor {
-        Cursor(token)
+    pub fn new(key: SortKey, uri: &str) -> Cursor {
+        Cursor { key, uri: uri.to_string() }
+    }
+
+    /// The timestamp and URI to continue after, for lists sorted by time.
+    pub fn timestamp(&self) -> Result<(DateTime<Utc>, &str)> {
+        match self.key {
+            SortKey::Timestamp(timestamp) => Ok((timestamp, &self.uri)),
+            _ => Err(wrong_list()),
+        }
+    }
+
+    /// The count and URI to continue after, for lists sorted by a count.
+    pub fn count(&self) -> Result<(i64, &str)> {
+        match self.key {
+            SortKey::Count(count) => Ok((count, &self.uri)),
+            _ => Err(wrong_list()),
+        }
+    }
+
+    /// The rank and URI to continue after, for search results.
+    pub fn rank(&self) -> Result<(f32, &str)> {
+        match self.key {
+            SortKey::Rank(rank) => Ok((rank, &self.uri)),
+            _ => Err(wrong_list()),
+        }
+    }
+
+    /// Encodes the cursor as an opaque token signed with `secret`.
+    pub fn encode(&self, secret: &str) -> String {
+        let key = match self.key {
+            SortKey::Timestamp(timestamp) => format!("t{}", timestamp.timestamp_micros()),
+            SortKey::Count(count) => format!("c{count}"),
+            // Ranks round-trip exactly as bits, unlike their decimal forms.
+            SortKey::Rank(rank) => format!("r{:x}", rank.to_bits()),
+        };
+        let payload = format!("{key} {}", self.uri);
+        let signature = mac(secret, payload.as_bytes()).finalize().into_bytes();
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(payload), URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Decodes a token made by `encode`, rejecting any that weren't signed
+    /// with `secret`.
+    pub fn decode(token: &str, secret: &str) -> Result<Cursor> {
+        let invalid = || BlogiError::InvalidRequest("Invalid cursor".to_string());
+        let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        mac(secret, &payload).verify_slice(&signature).map_err(|_| invalid())?;
+
+        // Anything signed was encoded by us, so from here it's well formed.
+        let payload = String::from_utf8(payload).map_err(|_| invalid())?;
+        let (key, uri) = payload.split_once(' ').ok_or_else(invalid)?;
+        let key = match key.split_at_checked(1).ok_or_else(invalid)? {
+            ("t", micros) => SortKey::Timestamp(
+                micros.parse().ok().and_then(DateTime::from_timestamp_micros).ok_or_else(invalid)?,
+            ),
+            ("c", count) => SortKey::Count(count.parse().map_err(|_| invalid())?),
+            ("r", bits) => SortKey::Rank(f32::from_bits(u32::from_str_radix(bits, 16).map_err(|_| invalid())?)),
+            _ => return Err(invalid()),
+        };
+        Ok(Cursor::new(key, uri))
+    }
+}
+
+fn mac(secret: &str, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(b"blogi cursor\0");
+    mac.update(payload);
+    mac
+}
+
+fn wrong_list() -> BlogiError {
+    BlogiError::InvalidRequest("Cursor is for a different list".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "secret";
+    const URI: &str = "at://did:plc:abc/moe.hayden.blogi.blog.entry/3k";
+
+    fn round_trip(key: SortKey) {
+        let cursor = Cursor::new(key, URI);
+        assert_eq!(Cursor::decode(&cursor.encode(SECRET), SECRET).unwrap(), cursor);
+    }
+
+    #[test]
+    fn sort_keys_round_trip() {
+        round_trip(SortKey::Timestamp(DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap()));
+        round_trip(SortKey::Count(0));
+        round_trip(SortKey::Count(-1));
+        round_trip(SortKey::Count(i64::MAX));
+        round_trip(SortKey::Rank(0.1));
+        round_trip(SortKey::Rank(f32::MIN_POSITIVE));
+    }
+
+    #[test]
+    fn uris_with_spaces_and_dots_round_trip() {
+        let cursor = Cursor::new(SortKey::Count(3), "at://did:web:example.com/a b.c");
+        assert_eq!(Cursor::decode(&cursor.encode(SECRET), SECRET).unwrap(), cursor);
+    }
+
+    #[test]
+    fn tampered_cursors_are_rejected() {
+        let token = Cursor::new(SortKey::Count(3), URI).encode(SECRET);
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}.{signature}", URL_SAFE_NO_PAD.encode(format!("c1000 {URI}")));
+        assert!(Cursor::decode(&forged, SECRET).is_err());
+
+        let mut flipped = token.clone().into_bytes();
+        let middle = token.find('.').unwrap() + signature.len() / 2;
+        flipped[middle] = if flipped[middle] == b'A' { b'B' } else { b'A' };
+        assert!(Cursor::decode(std::str::from_utf8(&flipped).unwrap(), SECRET).is_err());
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        for token in ["", ".", "abc", "abc.def", "!!!.!!!"] {
+            assert!(Cursor::decode(token, SECRET).is_err(), "{token:?}");
+        }
+    }
+
+    #[test]
+    fn cursors_signed_with_another_key_are_rejected() {
+        let token = Cursor::new(SortKey::Count(3), URI).encode("other secret");
+        assert!(Cursor::decode(&token, SECRET).is_err());
+    }
+
+    #[test]
+    fn cursors_only_continue_the_kind_of_list_they_came_from() {
+        let cursor = Cursor::new(SortKey::Count(3), URI);
+        assert_eq!(cursor.count().unwrap(), (3, URI));
+        assert!(cursor.timestamp().is_err());
+        assert!(cursor.rank().is_err());
     }
 }
@@ -0,0 +1,163 @@
+//! Converts records from repos into the rows they're indexed as.
+
+use atrium_api::types::{BlobRef, TypedBlobRef};
+use blogi_lexicons::moe::hayden::blogi::{
+    actor::profile,
+    blog::{comment, defs::Image, entry, like, series},
+    graph::follow,
+};
+use chrono::Utc;
+use sqlx::types::Json;
+
+use crate::{
+    actor::Profile,
+    comment::Comment,
+    entry::{Entry, EntryImage},
+    graph::Follow,
+    like::Like,
+    series::{Series, SeriesEntry},
+};
+
+pub const COMMENT_COLLECTION: &str = "moe.hayden.blogi.blog.comment";
+pub const ENTRY_COLLECTION: &str = "moe.hayden.blogi.blog.entry";
+pub const FOLLOW_COLLECTION: &str = "moe.hayden.blogi.graph.follow";
+pub const LIKE_COLLECTION: &str = "moe.hayden.blogi.blog.like";
+pub const PROFILE_COLLECTION: &str = "moe.hayden.blogi.actor.profile";
+pub const SERIES_COLLECTION: &str = "moe.hayden.blogi.blog.series";
+
+/// The record key at the end of an AT URI.
+pub fn rkey(uri: &str) -> &str {
+    uri.rsplit('/').next().unwrap_or_default()
+}
+
+/// Builds the row for an entry record. Counts are left at zero, since
+/// they're computed when entries are read.
+pub fn entry(did: &str, uri: &str, cid: &str, record: entry::RecordData) -> Entry {
+    let now = Utc::now();
+
+    Entry {
+        uri: uri.to_string(),
+        cid: cid.to_string(),
+        did: did.to_string(),
+        rkey: rkey(uri).to_string(),
+        title: record.title,
+        content: record.content,
+        content_format: record.content_format,
+        status: record.status,
+        visibility: record.visibility,
+        tags: record.tags.unwrap_or_default(),
+        langs: record.langs.unwrap_or_default().iter().map(|lang| lang.as_ref().to_string()).collect(),
+        bsky_post_uri: record.bsky_post_ref.as_ref().map(|post_ref| post_ref.uri.clone()),
+        bsky_post_cid: record.bsky_post_ref.as_ref().map(|post_ref| post_ref.cid.as_ref().to_string()),
+        publish_at: record.publish_at.map(|publish_at| publish_at.as_ref().to_utc()),
+        images: Json(record.images.unwrap_or_default().iter().map(entry_image).collect()),
+        comment_count: 0,
+        like_count: 0,
+        created_at: record.created_at.map_or(now, |created_at| created_at.as_ref().to_utc()),
+        updated_at: record.updated_at.map(|updated_at| updated_at.as_ref().to_utc()),
+        indexed_at: now,
+    }
+}
+
+fn entry_image(image: &Image) -> EntryImage {
+    // Old records may still use untyped blob refs, which don't give a size.
+    let (cid, mime_type, size) = match &image.image {
+        BlobRef::Typed(TypedBlobRef::Blob(blob)) => {
+            (blob.r#ref.0.to_string(), blob.mime_type.clone(), blob.size as i64)
+        }
+        BlobRef::Untyped(blob) => (blob.cid.clone(), blob.mime_type.clone(), 0),
+    };
+
+    EntryImage {
+        cid,
+        mime_type,
+        size,
+        alt: image.alt.clone(),
+        aspect_width: image.aspect_ratio.as_ref().map(|ratio| ratio.width.get() as i64),
+        aspect_height: image.aspect_ratio.as_ref().map(|ratio| ratio.height.get() as i64),
+    }
+}
+
+pub fn profile(did: &str, record: profile::RecordData) -> Profile {
+    Profile {
+        did: did.to_string(),
+        display_name: Some(record.display_name).filter(|name| !name.is_empty()),
+        description: record.description,
+        avatar_cid: record.avatar.as_ref().map(blob_cid),
+        banner_cid: record.banner.as_ref().map(blob_cid),
+        created_at: record.created_at.map(|created_at| created_at.as_ref().to_utc()),
+    }
+}
+
+fn blob_cid(blob: &BlobRef) -> String {
+    match blob {
+        BlobRef::Typed(TypedBlobRef::Blob(blob)) => blob.r#ref.0.to_string(),
+        BlobRef::Untyped(blob) => blob.cid.clone(),
+    }
+}
+
+pub fn follow(did: &str, uri: &str, cid: &str, record: follow::RecordData) -> Follow {
+    Follow {
+        uri: uri.to_string(),
+        cid: cid.to_string(),
+        did: did.to_string(),
+        rkey: rkey(uri).to_string(),
+        subject: record.subject.as_str().to_string(),
+        created_at: record.created_at.as_ref().to_utc(),
+        indexed_at: Utc::now(),
+    }
+}
+
+pub fn like(did: &str, uri: &str, cid: &str, record: like::RecordData) -> Like {
+    Like {
+        uri: uri.to_string(),
+        cid: cid.to_string(),
+        did: did.to_string(),
+        rkey: rkey(uri).to_string(),
+        subject_uri: record.subject.uri.clone(),
+        subject_cid: record.subject.cid.as_ref().to_string(),
+        created_at: record.created_at.as_ref().to_utc(),
+        indexed_at: Utc::now(),
+    }
+}
+
+pub fn series(did: &str, uri: &str, cid: &str, record: series::RecordData) -> Series {
+    let entries = record
+        .entries
+        .iter()
+        .map(|entry| SeriesEntry {
+            uri: entry.uri.clone(),
+            cid: entry.cid.as_ref().to_string(),
+        })
+        .collect();
+
+    Series {
+        uri: uri.to_string(),
+        cid: cid.to_string(),
+        did: did.to_string(),
+        rkey: rkey(uri).to_string(),
+        title: record.title,
+        description: record.description,
+        entries: Json(entries),
+        created_at: record.created_at.map(|created_at| created_at.as_ref().to_utc()),
+        updated_at: record.updated_at.map(|updated_at| updated_at.as_ref().to_utc()),
+        indexed_at: Utc::now(),
+    }
+}
+
+pub fn comment(did: &str, uri: &str, cid: &str, record: comment::RecordData) -> Comment {
+    let now = Utc::now();
+
+    Comment {
+        uri: uri.to_string(),
+        cid: cid.to_string(),
+        did: did.to_string(),
+        rkey: rkey(uri).to_string(),
+        post_uri: record.post.uri.clone(),
+        post_cid: record.post.cid.as_ref().to_string(),
+        content: record.content,
+        created_at: record.created_at.map_or(now, |created_at| created_at.as_ref().to_utc()),
+        updated_at: record.updated_at.map(|updated_at| updated_at.as_ref().to_utc()),
+        indexed_at: now,
+    }
+}
@@ -0,0 +1,267 @@
+use async_trait::async_trait;
+use blogi_errors::{Result, Success};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{query, query_as, types::Json};
+
+use crate::{
+    entry::{Entry, EntryImage},
+    pagination::Cursor,
+    pg::PostgresDatastore,
+};
+
+pub struct Series {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub rkey: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub entries: Json<Vec<SeriesEntry>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub indexed_at: DateTime<Utc>,
+}
+
+/// A strongRef from a series' `entries` array.
+#[derive(Deserialize)]
+pub struct SeriesEntry {
+    pub uri: String,
+    pub cid: String,
+}
+
+/// Where an entry sits within a series, counting only the series author's
+/// own indexed entries.
+pub struct SeriesMembership {
+    pub series_uri: String,
+    pub series_title: String,
+    /// 1-based.
+    pub position: i64,
+    pub total: i64,
+    pub prev_uri: Option<String>,
+    pub prev_title: Option<String>,
+    pub next_uri: Option<String>,
+    pub next_title: Option<String>,
+}
+
+#[async_trait]
+pub trait SeriesRepository {
+    async fn get_series(&self, uri: &str) -> Result<Option<Series>>;
+
+    /// Lists the series author's own indexed entries in a series that
+    /// `viewer` can see, in reading order. Cursors hold the 0-based position
+    /// of an entry in `series.entries`.
+    async fn list_series_entries(
+        &self,
+        series: &Series,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>>;
+
+    /// Counts the entries `list_series_entries` would list across all pages.
+    async fn count_series_entries(&self, series: &Series, viewer: Option<&str>) -> Result<i64>;
+
+    /// Lists the series an entry's author has placed it in. Positions and
+    /// neighbours only count entries `viewer` can see.
+    async fn list_series_memberships(&self, entry: &Entry, viewer: Option<&str>) -> Result<Vec<SeriesMembership>>;
+
+    /// Indexes a series, replacing any previous version of it.
+    async fn put_series(&self, series: &Series) -> Success;
+
+    /// Removes a series. Its entries are left as they are.
+    async fn delete_series(&self, uri: &str) -> Success;
+}
+
+#[async_trait]
+impl SeriesRepository for PostgresDatastore {
+    async fn get_series(&self, uri: &str) -> Result<Option<Series>> {
+        let series = query_as!(
+            Series,
+            r#"
+            SELECT
+                uri, cid, did, rkey, title, description, created_at, updated_at, indexed_at,
+                (
+                    SELECT coalesce(json_agg(json_build_object(
+                        'uri', entry_uri,
+                        'cid', entry_cid
+                    ) ORDER BY position), '[]')
+                    FROM series_entry
+                    WHERE series_uri = series.uri
+                ) AS "entries!: Json<Vec<SeriesEntry>>"
+            FROM series
+            WHERE uri = $1
+            "#,
+            uri,
+        )
+        .fetch_optional(&self.0)
+        .await?;
+
+        Ok(series)
+    }
+
+    async fn list_series_entries(
+        &self,
+        series: &Series,
+        viewer: Option<&str>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+    ) -> Result<Vec<Entry>> {
+        let after = cursor.map(Cursor::count).transpose()?.map(|(position, _)| position);
+
+        let entries = query_as!(
+            Entry,
+            r#"
+            SELECT
+                entry.uri, entry.cid, entry.did, entry.rkey, entry.title, entry.content,
+                entry.content_format, entry.status, entry.visibility, entry.tags, entry.langs,
+                entry.bsky_post_uri, entry.bsky_post_cid, entry.publish_at,
+                entry.created_at, entry.updated_at, entry.indexed_at,
+                entry_images(entry.uri) AS "images!: Json<Vec<EntryImage>>",
+                entry_comment_count(entry.uri) AS "comment_count!",
+                entry_like_count(entry.uri) AS "like_count!"
+            FROM series_entry
+            JOIN entry ON entry.uri = series_entry.entry_uri
+            WHERE series_entry.series_uri = $1 AND entry.did = $2
+                AND entry_visible(entry, $3)
+                AND ($4::BIGINT IS NULL OR series_entry.position > $4)
+            ORDER BY series_entry.position
+            LIMIT $5
+            "#,
+            series.uri,
+            series.did,
+            viewer,
+            after,
+            limit,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn count_series_entries(&self, series: &Series, viewer: Option<&str>) -> Result<i64> {
+        let row = query!(
+            r#"
+            SELECT count(*) AS "count!"
+            FROM series_entry
+            JOIN entry ON entry.uri = series_entry.entry_uri
+            WHERE series_entry.series_uri = $1 AND entry.did = $2
+                AND entry_visible(entry, $3)
+            "#,
+            series.uri,
+            series.did,
+            viewer,
+        )
+        .fetch_one(&self.0)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    async fn list_series_memberships(&self, entry: &Entry, viewer: Option<&str>) -> Result<Vec<SeriesMembership>> {
+        let memberships = query_as!(
+            SeriesMembership,
+            r#"
+            SELECT
+                series_uri AS "series_uri!",
+                series_title AS "series_title!",
+                position AS "position!",
+                total AS "total!",
+                prev_uri,
+                prev_title,
+                next_uri,
+                next_title
+            FROM (
+                SELECT
+                    series.uri AS series_uri,
+                    series.title AS series_title,
+                    entry.uri AS entry_uri,
+                    row_number() OVER w AS position,
+                    count(*) OVER (PARTITION BY series.uri) AS total,
+                    lag(entry.uri) OVER w AS prev_uri,
+                    lag(entry.title) OVER w AS prev_title,
+                    lead(entry.uri) OVER w AS next_uri,
+                    lead(entry.title) OVER w AS next_title
+                FROM series
+                JOIN series_entry ON series_entry.series_uri = series.uri
+                JOIN entry ON entry.uri = series_entry.entry_uri AND entry.did = series.did
+                    AND entry_visible(entry, $3)
+                WHERE series.did = $2
+                    AND series.uri IN (SELECT series_uri FROM series_entry WHERE entry_uri = $1)
+                WINDOW w AS (PARTITION BY series.uri ORDER BY series_entry.position)
+            ) members
+            WHERE entry_uri = $1
+            ORDER BY series_title
+            "#,
+            entry.uri,
+            entry.did,
+            viewer,
+        )
+        .fetch_all(&self.0)
+        .await?;
+
+        Ok(memberships)
+    }
+
+    async fn put_series(&self, series: &Series) -> Success {
+        let mut tx = self.0.begin().await?;
+
+        query!(
+            r#"
+            INSERT INTO series (uri, cid, did, rkey, title, description, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (uri) DO UPDATE SET
+                cid = excluded.cid,
+                title = excluded.title,
+                description = excluded.description,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                indexed_at = now()
+            "#,
+            series.uri,
+            series.cid,
+            series.did,
+            series.rkey,
+            series.title,
+            series.description,
+            series.created_at,
+            series.updated_at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        query!("DELETE FROM series_entry WHERE series_uri = $1", series.uri)
+            .execute(&mut *tx)
+            .await?;
+
+        // An entry listed twice keeps its first position.
+        let mut seen = std::collections::HashSet::new();
+        let entries = series.entries.iter().filter(|entry| seen.insert(&entry.uri));
+        for (position, entry) in entries.enumerate() {
+            query!(
+                r#"
+                INSERT INTO series_entry (series_uri, position, entry_uri, entry_cid)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                series.uri,
+                position as i32,
+                entry.uri,
+                entry.cid,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_series(&self, uri: &str) -> Success {
+        query!("DELETE FROM series WHERE uri = $1", uri)
+            .execute(&self.0)
+            .await?;
+
+        Ok(())
+    }
+}
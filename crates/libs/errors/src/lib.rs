@@ -4,7 +4,8 @@ extern crate axum;
 #[cfg(feature = "axum")]
 extern crate serde;
 
-use axum::Json;
+#[cfg(feature = "axum")]
+use axum::{http::StatusCode, Json};
 use thiserror::Error;
 
 pub type Result<T> = anyhow::Result<T, BlogiError>;
@@ -12,9 +13,39 @@ pub type Success = Result<()>;
 
 #[derive(Debug, Error)]
 pub enum BlogiError {
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("authentication required")]
+    AuthRequired,
+
+    #[error("invalid token: {0}")]
+    InvalidToken(String),
+
+    #[error("expired token")]
+    ExpiredToken,
+
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
     #[error("not found")]
     NotFound,
 
+    /// One of the errors a method declares in its lexicon, like
+    /// `InvalidSwap`.
+    #[error("{name}: {message}")]
+    Method { name: &'static str, message: String },
+
+    #[error("rate limit exceeded")]
+    RateLimitExceeded,
+
+    #[error("method not implemented: {0}")]
+    MethodNotImplemented(String),
+
+    /// A PDS, AppView or DID resolver we depend on failed.
+    #[error("upstream failure: {0}")]
+    UpstreamFailure(anyhow::Error),
+
     #[error("internal server error: {0}")]
     Internal(#[from] anyhow::Error),
 
@@ -22,8 +53,61 @@ pub enum BlogiError {
     DbErr(#[from] sqlx::Error)
 }
 
+impl BlogiError {
+    /// The name of the error, as it goes in an XRPC error response.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlogiError::InvalidRequest(_) => "InvalidRequest",
+            BlogiError::AuthRequired => "AuthenticationRequired",
+            BlogiError::InvalidToken(_) => "InvalidToken",
+            BlogiError::ExpiredToken => "ExpiredToken",
+            BlogiError::Forbidden(_) => "Forbidden",
+            BlogiError::NotFound => "NotFound",
+            BlogiError::Method { name, .. } => name,
+            BlogiError::RateLimitExceeded => "RateLimitExceeded",
+            BlogiError::MethodNotImplemented(_) => "MethodNotImplemented",
+            BlogiError::UpstreamFailure(_) => "UpstreamFailure",
+            BlogiError::Internal(_) | BlogiError::DbErr(_) => "InternalServerError",
+        }
+    }
+
+    /// A description of the error that's safe to show callers. Internal and
+    /// upstream details stay in the logs.
+    pub fn message(&self) -> Option<String> {
+        match self {
+            BlogiError::InvalidRequest(reason) | BlogiError::InvalidToken(reason) | BlogiError::Forbidden(reason) => {
+                Some(reason.clone())
+            }
+            BlogiError::AuthRequired => Some("This method requires authentication.".to_string()),
+            BlogiError::ExpiredToken => Some("The token has expired.".to_string()),
+            BlogiError::NotFound => Some("The requested resource was not found.".to_string()),
+            BlogiError::Method { message, .. } => Some(message.clone()),
+            BlogiError::RateLimitExceeded => Some("Too many requests; try again later.".to_string()),
+            BlogiError::MethodNotImplemented(nsid) => Some(format!("{nsid} isn't implemented by this service.")),
+            BlogiError::UpstreamFailure(_) => Some("A service this method depends on failed.".to_string()),
+            BlogiError::Internal(_) | BlogiError::DbErr(_) => None,
+        }
+    }
+
+    #[cfg(feature = "axum")]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BlogiError::InvalidRequest(_) | BlogiError::ExpiredToken | BlogiError::Method { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            BlogiError::AuthRequired | BlogiError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            BlogiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            BlogiError::NotFound => StatusCode::NOT_FOUND,
+            BlogiError::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            BlogiError::MethodNotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            BlogiError::UpstreamFailure(_) => StatusCode::BAD_GATEWAY,
+            BlogiError::Internal(_) | BlogiError::DbErr(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 #[cfg(feature = "axum")]
-#[cfg_attr(feature = "axum", derive(serde::Serialize))]
+#[derive(serde::Serialize)]
 pub struct XrpcErrorResponse {
     pub error: String,
     pub message: Option<String>,
@@ -32,21 +116,19 @@ pub struct XrpcErrorResponse {
 #[cfg(feature = "axum")]
 impl axum::response::IntoResponse for BlogiError {
     fn into_response(self) -> axum::response::Response {
-        let error = match self {
-            BlogiError::NotFound => "NotFound",
-            _ => "InternalServerError",
-        }.to_string();
-
-        let message = match self {
-            BlogiError::NotFound => Some("The requested resource was not found.".to_string()),
-            _ => None,
-        };
+        // Callers only get a generic message for these, so the details have
+        // to be logged here or they're lost.
+        match &self {
+            BlogiError::Internal(e) => tracing::error!("internal server error: {e:#}"),
+            BlogiError::UpstreamFailure(e) => tracing::error!("upstream failure: {e:#}"),
+            BlogiError::DbErr(e) => tracing::error!("database error: {e}"),
+            _ => {}
+        }
 
-        let code = match self {
-            BlogiError::NotFound => axum::http::StatusCode::NOT_FOUND,
-            _ => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+        let response = XrpcErrorResponse {
+            error: self.name().to_string(),
+            message: self.message(),
         };
-
-        ((code, Json(XrpcErrorResponse { error, message }))).into_response()
+        (self.status(), Json(response)).into_response()
     }
 }
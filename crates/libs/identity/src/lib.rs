@@ -0,0 +1,296 @@
+//! Resolves atproto identities: DIDs to their documents, and handles to
+//! DIDs.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use blogi_errors::{BlogiError, Result};
+use serde::Deserialize;
+
+/// How long a resolved DID document is trusted before it's fetched again.
+const DOCUMENT_TTL: Duration = Duration::from_secs(10 * 60);
+/// How soon after fetching a DID's document it may be refreshed. Anyone can
+/// send a badly signed token, and each would otherwise cost a fetch.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The multicodec prefixes of compressed public keys in `Multikey` form.
+pub const SECP256K1_PREFIX: [u8; 2] = [0xe7, 0x01];
+const P256_PREFIX: [u8; 2] = [0x80, 0x24];
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    pub id: String,
+    #[serde(default)]
+    pub also_known_as: Vec<String>,
+    #[serde(default)]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default)]
+    pub service: Vec<Service>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub public_key_multibase: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub service_endpoint: String,
+}
+
+/// A key an account signs with.
+#[derive(Clone)]
+pub enum SigningKey {
+    Secp256k1(k256::ecdsa::VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+}
+
+impl DidDocument {
+    /// Whether the account claims `handle`. Handles are only trusted when
+    /// they and the DID point at each other.
+    pub fn claims_handle(&self, handle: &str) -> bool {
+        self.also_known_as
+            .iter()
+            .any(|aka| aka.strip_prefix("at://").is_some_and(|aka| aka.eq_ignore_ascii_case(handle)))
+    }
+
+    /// The handle the account claims, unverified.
+    pub fn handle(&self) -> Option<&str> {
+        self.also_known_as.iter().find_map(|aka| aka.strip_prefix("at://"))
+    }
+
+    /// The URL of the account's PDS.
+    pub fn pds_endpoint(&self) -> Result<&str> {
+        self.service
+            .iter()
+            .find(|service| {
+                (service.id == "#atproto_pds" || service.id == format!("{}#atproto_pds", self.id))
+                    && service.kind == "AtprotoPersonalDataServer"
+            })
+            .map(|service| service.service_endpoint.trim_end_matches('/'))
+            .ok_or_else(|| anyhow!("{} has no PDS", self.id).into())
+    }
+
+    /// The account's atproto signing key, if it has a usable one.
+    pub fn signing_key(&self) -> Result<SigningKey> {
+        let method = self
+            .verification_method
+            .iter()
+            .find(|method| method.id == "#atproto" || method.id == format!("{}#atproto", self.id))
+            .ok_or_else(|| anyhow!("{} has no atproto signing key", self.id))?;
+        let multibase = method
+            .public_key_multibase
+            .as_deref()
+            .ok_or_else(|| anyhow!("{}'s signing key isn't in multibase form", self.id))?;
+        let (_, bytes) = multibase::decode(multibase).map_err(|e| anyhow!(e))?;
+
+        // Older documents name the curve in the method type and leave the
+        // multicodec prefix off.
+        let key = match method.kind.as_str() {
+            "Multikey" => match bytes.split_at_checked(2) {
+                Some((prefix, key)) if prefix == SECP256K1_PREFIX => {
+                    SigningKey::Secp256k1(k256::ecdsa::VerifyingKey::from_sec1_bytes(key).map_err(|e| anyhow!(e))?)
+                }
+                Some((prefix, key)) if prefix == P256_PREFIX => {
+                    SigningKey::P256(p256::ecdsa::VerifyingKey::from_sec1_bytes(key).map_err(|e| anyhow!(e))?)
+                }
+                _ => return Err(anyhow!("{}'s signing key uses an unsupported curve", self.id).into()),
+            },
+            "EcdsaSecp256k1VerificationKey2019" => {
+                SigningKey::Secp256k1(k256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes).map_err(|e| anyhow!(e))?)
+            }
+            "EcdsaSecp256r1VerificationKey2019" => {
+                SigningKey::P256(p256::ecdsa::VerifyingKey::from_sec1_bytes(&bytes).map_err(|e| anyhow!(e))?)
+            }
+            kind => return Err(anyhow!("{}'s signing key has unsupported type {kind}", self.id).into()),
+        };
+        Ok(key)
+    }
+}
+
+#[derive(Deserialize)]
+struct ResolveHandleOutput {
+    did: String,
+}
+
+/// Resolves `did:plc` and `did:web` identities to their documents, and
+/// handles to DIDs.
+pub struct DidResolver {
+    http: reqwest::Client,
+    plc_directory_url: String,
+    appview_url: String,
+    documents: moka::future::Cache<String, DidDocument>,
+    /// DIDs whose documents were fetched within the last `REFRESH_INTERVAL`.
+    fetched: moka::future::Cache<String, ()>,
+}
+
+impl DidResolver {
+    pub fn new(http: reqwest::Client, plc_directory_url: &str, appview_url: &str) -> DidResolver {
+        DidResolver {
+            http,
+            plc_directory_url: plc_directory_url.trim_end_matches('/').to_string(),
+            appview_url: appview_url.trim_end_matches('/').to_string(),
+            documents: moka::future::Cache::builder()
+                .time_to_live(DOCUMENT_TTL)
+                .max_capacity(10_000)
+                .build(),
+            fetched: moka::future::Cache::builder()
+                .time_to_live(REFRESH_INTERVAL)
+                .max_capacity(10_000)
+                .build(),
+        }
+    }
+
+    /// Resolves a DID, using a cached document if there is one.
+    pub async fn resolve(&self, did: &str) -> Result<DidDocument> {
+        if let Some(document) = self.documents.get(did).await {
+            return Ok(document);
+        }
+        self.fetched.insert(did.to_string(), ()).await;
+        self.fetch(did).await
+    }
+
+    /// Resolves a DID, skipping the cache. Used when a cached document
+    /// might be stale, such as after its key fails to verify a signature.
+    /// Returns `None` if the document was fetched too recently to be worth
+    /// fetching again.
+    pub async fn refresh(&self, did: &str) -> Result<Option<DidDocument>> {
+        if !self.fetched.entry(did.to_string()).or_insert(()).await.is_fresh() {
+            return Ok(None);
+        }
+        self.fetch(did).await.map(Some)
+    }
+
+    async fn fetch(&self, did: &str) -> Result<DidDocument> {
+        let url = if did.starts_with("did:plc:") {
+            format!("{}/{did}", self.plc_directory_url)
+        } else if let Some(host) = did.strip_prefix("did:web:") {
+            // did:web only supports hosts, with any port percent-encoded.
+            if host.contains(':') {
+                return Err(anyhow!("did:web with a path isn't supported: {did}").into());
+            }
+            format!("https://{}/.well-known/did.json", host.replace("%3A", ":"))
+        } else {
+            return Err(anyhow!("unsupported DID method: {did}").into());
+        };
+
+        let response = self
+            .http
+            .get(url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| BlogiError::UpstreamFailure(e.into()))?;
+        if !response.status().is_success() {
+            return Err(BlogiError::UpstreamFailure(anyhow!("resolving {did} failed with {}", response.status())));
+        }
+        let document: DidDocument = response.json().await.map_err(|e| anyhow!(e))?;
+        if document.id != did {
+            return Err(anyhow!("document for {did} is for {}", document.id).into());
+        }
+
+        self.documents.insert(did.to_string(), document.clone()).await;
+        Ok(document)
+    }
+
+    /// Resolves a handle to the DID it claims. Callers should check the
+    /// DID's document claims the handle back before trusting it.
+    pub async fn resolve_handle(&self, handle: &str) -> Result<String> {
+        let handle = handle.trim().trim_start_matches('@').to_ascii_lowercase();
+        if !handle.contains('.') || !handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+            return Err(anyhow!("{handle} isn't a valid handle").into());
+        }
+
+        // Handles are usually published over HTTPS; ones only published in
+        // DNS are left to the AppView.
+        let well_known = self
+            .http
+            .get(format!("https://{handle}/.well-known/atproto-did"))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await;
+        if let Ok(response) = well_known.and_then(|response| response.error_for_status())
+            && let Ok(did) = response.text().await
+            && did.trim().starts_with("did:")
+        {
+            return Ok(did.trim().to_string());
+        }
+
+        let response = self
+            .http
+            .get(format!("{}/xrpc/com.atproto.identity.resolveHandle", self.appview_url))
+            .query(&[("handle", &handle)])
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| BlogiError::UpstreamFailure(e.into()))?;
+        if !response.status().is_success() {
+            return Err(BlogiError::UpstreamFailure(anyhow!("resolving {handle} failed with {}", response.status())));
+        }
+        let output: ResolveHandleOutput = response.json().await.map_err(|e| anyhow!(e))?;
+        Ok(output.did)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serves a document for whichever DID is asked for, counting requests.
+    async fn plc_directory() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut request = [0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let did = request.split(' ').nth(1).unwrap_or_default().trim_start_matches('/');
+                let body = format!(r#"{{"id":"{did}"}}"#);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len(),
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn refreshes_are_limited_per_did() {
+        let (url, requests) = plc_directory().await;
+        let resolver = DidResolver::new(reqwest::Client::new(), &url, &url);
+
+        resolver.resolve("did:plc:alice").await.unwrap();
+        assert!(resolver.refresh("did:plc:alice").await.unwrap().is_none(), "it was only just fetched");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let bob = resolver.refresh("did:plc:bob").await.unwrap();
+        assert_eq!(bob.map(|document| document.id).as_deref(), Some("did:plc:bob"));
+        assert!(resolver.refresh("did:plc:bob").await.unwrap().is_none());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
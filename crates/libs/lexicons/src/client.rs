@@ -1,4 +1,4 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Structs for ATP client, implements all HTTP APIs of XRPC.
 ///Client struct for the ATP service.
 pub struct AtpServiceClient<T>
@@ -172,6 +172,67 @@ where
             _phantom: core::marker::PhantomData,
         }
     }
+    ///Get the comments on a post, oldest first, including replies to its Bluesky announcement.
+    pub async fn get_comments(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_comments::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_comments::Output,
+        crate::moe::hayden::blogi::blog::get_comments::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_comments::NSID.into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Get a list of posts with a given tag, newest first.
+    pub async fn get_entries_by_tag(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_entries_by_tag::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_entries_by_tag::Output,
+        crate::moe::hayden::blogi::blog::get_entries_by_tag::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_entries_by_tag::NSID
+                        .into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
     ///Get a list of posts for a specific account.
     pub async fn get_entries_for_author(
         &self,
@@ -203,4 +264,186 @@ where
             _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
         }
     }
+    ///Get the versions a post had before it was last edited, newest first. The current version is the post itself.
+    pub async fn get_entry_revisions(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_entry_revisions::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_entry_revisions::Output,
+        crate::moe::hayden::blogi::blog::get_entry_revisions::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_entry_revisions::NSID
+                        .into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Get posts by the authors the requesting account follows, newest first. Requires auth.
+    pub async fn get_following_feed(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_following_feed::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_following_feed::Output,
+        crate::moe::hayden::blogi::blog::get_following_feed::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_following_feed::NSID
+                        .into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Get the accounts that have liked a post, most recent first.
+    pub async fn get_likes(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_likes::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_likes::Output,
+        crate::moe::hayden::blogi::blog::get_likes::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_likes::NSID.into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Get a series and its posts, in reading order.
+    pub async fn get_series(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_series::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_series::Output,
+        crate::moe::hayden::blogi::blog::get_series::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_series::NSID.into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Get public posts by every indexed author, for discovery.
+    pub async fn get_timeline(
+        &self,
+        params: crate::moe::hayden::blogi::blog::get_timeline::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::get_timeline::Output,
+        crate::moe::hayden::blogi::blog::get_timeline::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::get_timeline::NSID.into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
+    ///Find posts matching a full-text search query, best matches first.
+    pub async fn search_entries(
+        &self,
+        params: crate::moe::hayden::blogi::blog::search_entries::Parameters,
+    ) -> atrium_xrpc::Result<
+        crate::moe::hayden::blogi::blog::search_entries::Output,
+        crate::moe::hayden::blogi::blog::search_entries::Error,
+    > {
+        let response = self
+            .xrpc
+            .send_xrpc::<
+                _,
+                (),
+                _,
+                _,
+            >(
+                &atrium_xrpc::XrpcRequest {
+                    method: http::Method::GET,
+                    nsid: crate::moe::hayden::blogi::blog::search_entries::NSID.into(),
+                    parameters: Some(params),
+                    input: None,
+                    encoding: None,
+                },
+            )
+            .await?;
+        match response {
+            atrium_xrpc::OutputDataOrBytes::Data(data) => Ok(data),
+            _ => Err(atrium_xrpc::Error::UnexpectedResponseType),
+        }
+    }
 }
@@ -1,5 +1,7 @@
 // @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
 pub mod record;
 pub mod client;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod moe;
 pub mod com;
@@ -1,4 +1,5 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi` namespace.
 pub mod actor;
 pub mod blog;
+pub mod graph;
@@ -1,4 +1,4 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi.actor.defs` namespace.
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -33,6 +33,10 @@ pub struct ProfileViewDetailedData {
     pub did: atrium_api::types::string::Did,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub display_name: core::option::Option<String>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub followers_count: core::option::Option<i64>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub follows_count: core::option::Option<i64>,
     pub handle: atrium_api::types::string::Handle,
     pub indexed_at: atrium_api::types::string::Datetime,
     pub posts_count: i64,
@@ -1,9 +1,19 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi.blog` namespace.
 pub mod comment;
 pub mod defs;
 pub mod entry;
+pub mod get_comments;
+pub mod get_entries_by_tag;
 pub mod get_entries_for_author;
+pub mod get_entry_revisions;
+pub mod get_following_feed;
+pub mod get_likes;
+pub mod get_series;
+pub mod get_timeline;
+pub mod like;
+pub mod search_entries;
+pub mod series;
 #[derive(Debug)]
 pub struct Comment;
 impl atrium_api::types::Collection for Comment {
@@ -16,3 +26,15 @@ impl atrium_api::types::Collection for Entry {
     const NSID: &'static str = "moe.hayden.blogi.blog.entry";
     type Record = entry::Record;
 }
+#[derive(Debug)]
+pub struct Like;
+impl atrium_api::types::Collection for Like {
+    const NSID: &'static str = "moe.hayden.blogi.blog.like";
+    type Record = like::Record;
+}
+#[derive(Debug)]
+pub struct Series;
+impl atrium_api::types::Collection for Series {
+    const NSID: &'static str = "moe.hayden.blogi.blog.series";
+    type Record = series::Record;
+}
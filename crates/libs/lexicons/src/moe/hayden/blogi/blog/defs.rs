@@ -1,5 +1,65 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi.blog.defs` namespace.
+///width:height represents an aspect ratio. It may be approximate, and may not correspond to absolute dimensions in any given unit.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AspectRatioData {
+    pub height: core::num::NonZeroU64,
+    pub width: core::num::NonZeroU64,
+}
+pub type AspectRatio = atrium_api::types::Object<AspectRatioData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAuthorData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub avatar: core::option::Option<String>,
+    pub did: atrium_api::types::string::Did,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub display_name: core::option::Option<String>,
+    ///Unset if the commenter isn't known to this service.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub handle: core::option::Option<atrium_api::types::string::Handle>,
+}
+pub type CommentAuthor = atrium_api::types::Object<CommentAuthorData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentViewData {
+    pub author: CommentAuthor,
+    pub cid: atrium_api::types::string::Cid,
+    pub content: String,
+    pub created_at: atrium_api::types::string::Datetime,
+    ///The Bluesky reply this one responds to, if it isn't a direct reply to the announcement.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub parent: core::option::Option<String>,
+    ///`blogi` for comment records, `bluesky` for replies to the post's bskyPostRef.
+    pub source: String,
+    pub uri: String,
+}
+pub type CommentView = atrium_api::types::Object<CommentViewData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageData {
+    ///Alt text description of the image, for accessibility.
+    pub alt: String,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub aspect_ratio: core::option::Option<AspectRatio>,
+    pub image: atrium_api::types::BlobRef,
+}
+pub type Image = atrium_api::types::Object<ImageData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageViewData {
+    ///Alt text description of the image, for accessibility.
+    pub alt: String,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub aspect_ratio: core::option::Option<AspectRatio>,
+    pub cid: atrium_api::types::string::Cid,
+    ///URL of a large version of the image, served by the blob proxy.
+    pub fullsize: String,
+    ///URL of a thumbnail of the image, served by the blob proxy.
+    pub thumb: String,
+}
+pub type ImageView = atrium_api::types::Object<ImageViewData>;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct PostViewData {
@@ -8,17 +68,93 @@ pub struct PostViewData {
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub comment_count: core::option::Option<i64>,
     pub indexed_at: atrium_api::types::string::Datetime,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub like_count: core::option::Option<i64>,
     pub record: PostViewRecord,
+    ///Series the post is part of. Only set when viewing a single post.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub series: core::option::Option<Vec<SeriesMembership>>,
     pub uri: String,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub viewer: core::option::Option<ViewerState>,
 }
 pub type PostView = atrium_api::types::Object<PostViewData>;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct PostViewRecordData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub bsky_post_ref: core::option::Option<crate::com::atproto::repo::strong_ref::Main>,
     pub content: String,
+    pub content_format: String,
     pub created_at: atrium_api::types::string::Datetime,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub images: core::option::Option<Vec<ImageView>>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub publish_at: core::option::Option<atrium_api::types::string::Datetime>,
+    ///Drafts are only shown to their author.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub status: core::option::Option<String>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub tags: core::option::Option<Vec<String>>,
     pub title: String,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub updated_at: core::option::Option<atrium_api::types::string::Datetime>,
+    ///Unlisted posts are left out of listings, feeds and search, except for their author.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub visibility: core::option::Option<String>,
 }
 pub type PostViewRecord = atrium_api::types::Object<PostViewRecordData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultData {
+    pub post: PostView,
+    ///HTML excerpt of the post's content around the matches, which are wrapped in <mark> elements.
+    pub snippet: String,
+}
+pub type SearchResult = atrium_api::types::Object<SearchResultData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesEntryLinkData {
+    pub title: String,
+    pub uri: String,
+}
+pub type SeriesEntryLink = atrium_api::types::Object<SeriesEntryLinkData>;
+///Where a post sits within a series.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesMembershipData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub next: core::option::Option<SeriesEntryLink>,
+    ///The post's 1-based position in the series.
+    pub position: i64,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub prev: core::option::Option<SeriesEntryLink>,
+    pub title: String,
+    pub total: i64,
+    ///AT URI of the series record.
+    pub uri: String,
+}
+pub type SeriesMembership = atrium_api::types::Object<SeriesMembershipData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesViewData {
+    pub author: crate::moe::hayden::blogi::actor::defs::ProfileView,
+    pub cid: atrium_api::types::string::Cid,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub created_at: core::option::Option<atrium_api::types::string::Datetime>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub description: core::option::Option<String>,
+    pub entry_count: i64,
+    pub indexed_at: atrium_api::types::string::Datetime,
+    pub title: String,
+    pub uri: String,
+}
+pub type SeriesView = atrium_api::types::Object<SeriesViewData>;
+///Metadata about the requesting account's relationship with a post. Only set on authenticated requests.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerStateData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub like: core::option::Option<String>,
+}
+pub type ViewerState = atrium_api::types::Object<ViewerStateData>;
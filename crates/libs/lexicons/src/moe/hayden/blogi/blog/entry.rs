@@ -1,14 +1,34 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi.blog.entry` namespace.
 use atrium_api::types::TryFromUnknown;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct RecordData {
+    ///A Bluesky post announcing the entry. Replies to it are shown alongside comments.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub bsky_post_ref: core::option::Option<crate::com::atproto::repo::strong_ref::Main>,
     pub content: String,
+    ///The media type of `content`. Entries without one are treated as Markdown, and HTML is sanitized before display.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub content_format: core::option::Option<String>,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub created_at: core::option::Option<atrium_api::types::string::Datetime>,
+    ///Images attached to the entry. Content can place them inline with a Markdown image pointing at `blob:<cid>`.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub images: core::option::Option<
+        Vec<crate::moe::hayden::blogi::blog::defs::Image>,
+    >,
+    ///Languages the entry is written in, main one first. Search stems words by the first.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub langs: core::option::Option<Vec<atrium_api::types::string::Language>>,
+    ///When the entry goes live. Until then it's treated as unpublished.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub publish_at: core::option::Option<atrium_api::types::string::Datetime>,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub status: core::option::Option<String>,
+    ///Topics the entry is filed under.
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub tags: core::option::Option<Vec<String>>,
     pub title: String,
     #[serde(skip_serializing_if = "core::option::Option::is_none")]
     pub updated_at: core::option::Option<atrium_api::types::string::Datetime>,
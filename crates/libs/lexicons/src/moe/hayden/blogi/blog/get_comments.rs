@@ -0,0 +1,30 @@
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
+//!Definitions for the `moe.hayden.blogi.blog.getComments` namespace.
+pub const NSID: &str = "moe.hayden.blogi.blog.getComments";
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParametersData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub cursor: core::option::Option<String>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub limit: core::option::Option<atrium_api::types::LimitedNonZeroU8<100u8>>,
+    ///AT URI of the post.
+    pub uri: String,
+}
+pub type Parameters = atrium_api::types::Object<ParametersData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub cursor: core::option::Option<String>,
+    pub comments: Vec<crate::moe::hayden::blogi::blog::defs::CommentView>,
+}
+pub type Output = atrium_api::types::Object<OutputData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "error", content = "message")]
+pub enum Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        Ok(())
+    }
+}
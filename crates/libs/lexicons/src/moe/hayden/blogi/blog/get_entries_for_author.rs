@@ -1,4 +1,4 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!Definitions for the `moe.hayden.blogi.blog.getEntriesForAuthor` namespace.
 pub const NSID: &str = "moe.hayden.blogi.blog.getEntriesForAuthor";
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -6,11 +6,17 @@ pub const NSID: &str = "moe.hayden.blogi.blog.getEntriesForAuthor";
 pub struct ParametersData {
     ///Handle or DID of account to fetch posts of.
     pub author_did: atrium_api::types::string::AtIdentifier,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub cursor: core::option::Option<String>,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub limit: core::option::Option<atrium_api::types::LimitedNonZeroU8<100u8>>,
 }
 pub type Parameters = atrium_api::types::Object<ParametersData>;
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct OutputData {
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub cursor: core::option::Option<String>,
     pub posts: Vec<crate::moe::hayden::blogi::blog::defs::PostView>,
 }
 pub type Output = atrium_api::types::Object<OutputData>;
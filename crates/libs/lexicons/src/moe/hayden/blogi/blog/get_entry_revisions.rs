@@ -0,0 +1,40 @@
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
+//!Definitions for the `moe.hayden.blogi.blog.getEntryRevisions` namespace.
+pub const NSID: &str = "moe.hayden.blogi.blog.getEntryRevisions";
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParametersData {
+    ///AT URI of the post.
+    pub uri: String,
+}
+pub type Parameters = atrium_api::types::Object<ParametersData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputData {
+    pub revisions: Vec<Revision>,
+    pub uri: String,
+}
+pub type Output = atrium_api::types::Object<OutputData>;
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "error", content = "message")]
+pub enum Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        Ok(())
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionData {
+    pub cid: atrium_api::types::string::Cid,
+    pub content: String,
+    pub content_format: String,
+    ///When this version was first seen.
+    pub indexed_at: atrium_api::types::string::Datetime,
+    ///When a newer version replaced this one.
+    pub replaced_at: atrium_api::types::string::Datetime,
+    pub title: String,
+    #[serde(skip_serializing_if = "core::option::Option::is_none")]
+    pub updated_at: core::option::Option<atrium_api::types::string::Datetime>,
+}
+pub type Revision = atrium_api::types::Object<RevisionData>;
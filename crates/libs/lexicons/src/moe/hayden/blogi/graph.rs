@@ -0,0 +1,9 @@
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
+//!Definitions for the `moe.hayden.blogi.graph` namespace.
+pub mod follow;
+#[derive(Debug)]
+pub struct Follow;
+impl atrium_api::types::Collection for Follow {
+    const NSID: &'static str = "moe.hayden.blogi.graph.follow";
+    type Record = follow::Record;
+}
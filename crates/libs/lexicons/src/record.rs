@@ -1,4 +1,4 @@
-// @generated - This file is generated by esquema-codegen (forked from atrium-codegen). DO NOT EDIT.
+// Written by hand to match what esquema-codegen generates from the lexicons; `just lexgen-rs` replaces it with the generated version.
 //!A collection of known record types.
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "$type")]
@@ -9,6 +9,12 @@ pub enum KnownRecord {
     MoeHaydenBlogiBlogComment(Box<crate::moe::hayden::blogi::blog::comment::Record>),
     #[serde(rename = "moe.hayden.blogi.blog.entry")]
     MoeHaydenBlogiBlogEntry(Box<crate::moe::hayden::blogi::blog::entry::Record>),
+    #[serde(rename = "moe.hayden.blogi.blog.like")]
+    MoeHaydenBlogiBlogLike(Box<crate::moe::hayden::blogi::blog::like::Record>),
+    #[serde(rename = "moe.hayden.blogi.blog.series")]
+    MoeHaydenBlogiBlogSeries(Box<crate::moe::hayden::blogi::blog::series::Record>),
+    #[serde(rename = "moe.hayden.blogi.graph.follow")]
+    MoeHaydenBlogiGraphFollow(Box<crate::moe::hayden::blogi::graph::follow::Record>),
 }
 impl From<crate::moe::hayden::blogi::actor::profile::Record> for KnownRecord {
     fn from(record: crate::moe::hayden::blogi::actor::profile::Record) -> Self {
@@ -40,6 +46,36 @@ impl From<crate::moe::hayden::blogi::blog::entry::RecordData> for KnownRecord {
         KnownRecord::MoeHaydenBlogiBlogEntry(Box::new(record_data.into()))
     }
 }
+impl From<crate::moe::hayden::blogi::blog::like::Record> for KnownRecord {
+    fn from(record: crate::moe::hayden::blogi::blog::like::Record) -> Self {
+        KnownRecord::MoeHaydenBlogiBlogLike(Box::new(record))
+    }
+}
+impl From<crate::moe::hayden::blogi::blog::like::RecordData> for KnownRecord {
+    fn from(record_data: crate::moe::hayden::blogi::blog::like::RecordData) -> Self {
+        KnownRecord::MoeHaydenBlogiBlogLike(Box::new(record_data.into()))
+    }
+}
+impl From<crate::moe::hayden::blogi::blog::series::Record> for KnownRecord {
+    fn from(record: crate::moe::hayden::blogi::blog::series::Record) -> Self {
+        KnownRecord::MoeHaydenBlogiBlogSeries(Box::new(record))
+    }
+}
+impl From<crate::moe::hayden::blogi::blog::series::RecordData> for KnownRecord {
+    fn from(record_data: crate::moe::hayden::blogi::blog::series::RecordData) -> Self {
+        KnownRecord::MoeHaydenBlogiBlogSeries(Box::new(record_data.into()))
+    }
+}
+impl From<crate::moe::hayden::blogi::graph::follow::Record> for KnownRecord {
+    fn from(record: crate::moe::hayden::blogi::graph::follow::Record) -> Self {
+        KnownRecord::MoeHaydenBlogiGraphFollow(Box::new(record))
+    }
+}
+impl From<crate::moe::hayden::blogi::graph::follow::RecordData> for KnownRecord {
+    fn from(record_data: crate::moe::hayden::blogi::graph::follow::RecordData) -> Self {
+        KnownRecord::MoeHaydenBlogiGraphFollow(Box::new(record_data.into()))
+    }
+}
 impl Into<atrium_api::types::Unknown> for KnownRecord {
     fn into(self) -> atrium_api::types::Unknown {
         atrium_api::types::TryIntoUnknown::try_into_unknown(&self).unwrap()
@@ -0,0 +1,333 @@
+// @generated - This file is generated by blogi-lexgen (`just lexgen-server`). DO NOT EDIT.
+//!Traits for an ATP server, routes all HTTP APIs of XRPC.
+///Every XRPC method in the lexicons, all of which `router` mounts.
+pub const NSIDS: &[&str] = &[
+    crate::moe::hayden::blogi::actor::get_profile::NSID,
+    crate::moe::hayden::blogi::blog::get_comments::NSID,
+    crate::moe::hayden::blogi::blog::get_entries_by_tag::NSID,
+    crate::moe::hayden::blogi::blog::get_entries_for_author::NSID,
+    crate::moe::hayden::blogi::blog::get_entry_revisions::NSID,
+    crate::moe::hayden::blogi::blog::get_following_feed::NSID,
+    crate::moe::hayden::blogi::blog::get_likes::NSID,
+    crate::moe::hayden::blogi::blog::get_series::NSID,
+    crate::moe::hayden::blogi::blog::get_timeline::NSID,
+    crate::moe::hayden::blogi::blog::search_entries::NSID,
+];
+///Server trait for the ATP service. Methods that aren't implemented respond with `MethodNotImplemented`.
+#[async_trait::async_trait]
+#[allow(unused_variables)]
+pub trait XrpcServer: Clone + Send + Sync + 'static {
+    ///Extracted from each request and passed to its method, such as the authenticated caller.
+    type Context: axum::extract::FromRequestParts<Self, Rejection = Self::Error> + Send;
+    type Error: axum::response::IntoResponse + Send;
+    fn invalid_request(message: String) -> Self::Error;
+    fn method_not_implemented(nsid: &'static str) -> Self::Error;
+    ///Get detailed profile view of an actor. Does not require auth, but contains relevant metadata with auth.
+    async fn actor_get_profile(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::actor::get_profile::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::actor::get_profile::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::actor::get_profile::NSID))
+    }
+    ///Get the comments on a post, oldest first, including replies to its Bluesky announcement.
+    async fn blog_get_comments(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_comments::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_comments::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_comments::NSID))
+    }
+    ///Get a list of posts with a given tag, newest first.
+    async fn blog_get_entries_by_tag(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_entries_by_tag::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_entries_by_tag::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_entries_by_tag::NSID))
+    }
+    ///Get a list of posts for a specific account.
+    async fn blog_get_entries_for_author(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_entries_for_author::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_entries_for_author::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_entries_for_author::NSID))
+    }
+    ///Get the versions a post had before it was last edited, newest first. The current version is the post itself.
+    async fn blog_get_entry_revisions(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_entry_revisions::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_entry_revisions::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_entry_revisions::NSID))
+    }
+    ///Get posts by the authors the requesting account follows, newest first. Requires auth.
+    async fn blog_get_following_feed(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_following_feed::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_following_feed::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_following_feed::NSID))
+    }
+    ///Get the accounts that have liked a post, most recent first.
+    async fn blog_get_likes(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_likes::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_likes::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_likes::NSID))
+    }
+    ///Get a series and its posts, in reading order.
+    async fn blog_get_series(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_series::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_series::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_series::NSID))
+    }
+    ///Get public posts by every indexed author, for discovery.
+    async fn blog_get_timeline(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::get_timeline::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::get_timeline::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::get_timeline::NSID))
+    }
+    ///Find posts matching a full-text search query, best matches first.
+    async fn blog_search_entries(
+        &self,
+        context: Self::Context,
+        params: crate::moe::hayden::blogi::blog::search_entries::Parameters,
+    ) -> core::result::Result<crate::moe::hayden::blogi::blog::search_entries::Output, Self::Error> {
+        Err(Self::method_not_implemented(crate::moe::hayden::blogi::blog::search_entries::NSID))
+    }
+}
+///Builds a router that mounts every XRPC method at `/xrpc/<nsid>`.
+pub fn router<S: XrpcServer>() -> axum::Router<S> {
+    axum::Router::new()
+        .route(
+            "/xrpc/moe.hayden.blogi.actor.getProfile",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::actor::get_profile::Parameters>| async move {
+                    server.actor_get_profile(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getComments",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_comments::Parameters>| async move {
+                    server.blog_get_comments(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getEntriesByTag",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_entries_by_tag::Parameters>| async move {
+                    server.blog_get_entries_by_tag(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getEntriesForAuthor",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_entries_for_author::Parameters>| async move {
+                    server.blog_get_entries_for_author(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getEntryRevisions",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_entry_revisions::Parameters>| async move {
+                    server.blog_get_entry_revisions(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getFollowingFeed",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_following_feed::Parameters>| async move {
+                    server.blog_get_following_feed(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getLikes",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_likes::Parameters>| async move {
+                    server.blog_get_likes(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getSeries",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_series::Parameters>| async move {
+                    server.blog_get_series(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.getTimeline",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::get_timeline::Parameters>| async move {
+                    server.blog_get_timeline(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+        .route(
+            "/xrpc/moe.hayden.blogi.blog.searchEntries",
+            axum::routing::get(
+                |axum::extract::State(server): axum::extract::State<S>,
+                 context: S::Context,
+                 XrpcParams(params): XrpcParams<crate::moe::hayden::blogi::blog::search_entries::Parameters>| async move {
+                    server.blog_search_entries(context, params).await.map(axum::Json)
+                },
+            ),
+        )
+}
+///Query parameters of an XRPC method, checked against its lexicon.
+pub struct XrpcParams<T>(pub T);
+impl<S, T> axum::extract::FromRequestParts<S> for XrpcParams<atrium_api::types::Object<T>>
+where
+    S: XrpcServer,
+    T: serde::de::DeserializeOwned + Validate,
+{
+    type Rejection = S::Error;
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> core::result::Result<Self, Self::Rejection> {
+        //`Object` flattens its data, which hides the integers in a query string from serde.
+        let params: T = serde_html_form::from_str(parts.uri.query().unwrap_or_default())
+            .map_err(|e| S::invalid_request(e.to_string()))?;
+        params.validate().map_err(S::invalid_request)?;
+        Ok(XrpcParams(params.into()))
+    }
+}
+///Checks the constraints on parameters that their types don't enforce.
+pub trait Validate {
+    fn validate(&self) -> core::result::Result<(), String> {
+        Ok(())
+    }
+}
+///Checks `value` is an AT URI: an account, optionally followed by a collection and a record key.
+fn validate_at_uri(name: &str, value: &str) -> core::result::Result<(), String> {
+    let invalid = || format!("{name} must be an AT URI");
+    let mut segments = value.strip_prefix("at://").ok_or_else(invalid)?.split('/');
+    segments
+        .next()
+        .unwrap_or_default()
+        .parse::<atrium_api::types::string::AtIdentifier>()
+        .map_err(|_| invalid())?;
+    if let Some(collection) = segments.next() {
+        atrium_api::types::string::Nsid::new(collection.to_string()).map_err(|_| invalid())?;
+    }
+    if let Some(rkey) = segments.next() {
+        atrium_api::types::string::RecordKey::new(rkey.to_string()).map_err(|_| invalid())?;
+    }
+    match segments.next() {
+        Some(_) => Err(invalid()),
+        None => Ok(()),
+    }
+}
+///Checks the length of `value` in bytes, as `minLength` and `maxLength` count it.
+fn validate_length(name: &str, value: &str, min: usize, max: usize) -> core::result::Result<(), String> {
+    if value.len() < min {
+        return Err(format!("{name} must be at least {min} bytes long"));
+    }
+    if value.len() > max {
+        return Err(format!("{name} must be at most {max} bytes long"));
+    }
+    Ok(())
+}
+///Checks the length of `value` in graphemes, as `minGraphemes` and `maxGraphemes` count it.
+fn validate_graphemes(name: &str, value: &str, min: usize, max: usize) -> core::result::Result<(), String> {
+    let graphemes = unicode_segmentation::UnicodeSegmentation::graphemes(value, true).count();
+    if graphemes < min {
+        return Err(format!("{name} must be at least {min} characters long"));
+    }
+    if graphemes > max {
+        return Err(format!("{name} must be at most {max} characters long"));
+    }
+    Ok(())
+}
+///Checks `value` is one of the values a lexicon allows.
+fn validate_enum(name: &str, value: &str, values: &[&str]) -> core::result::Result<(), String> {
+    if values.contains(&value) {
+        return Ok(());
+    }
+    Err(format!("{name} must be one of: {}", values.join(", ")))
+}
+impl Validate for crate::moe::hayden::blogi::actor::get_profile::ParametersData {}
+impl Validate for crate::moe::hayden::blogi::blog::get_comments::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_at_uri("uri", &self.uri)?;
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::get_entries_by_tag::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_length("tag", &self.tag, 0, 640)?;
+        validate_graphemes("tag", &self.tag, 0, 64)?;
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::get_entries_for_author::ParametersData {}
+impl Validate for crate::moe::hayden::blogi::blog::get_entry_revisions::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_at_uri("uri", &self.uri)?;
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::get_following_feed::ParametersData {}
+impl Validate for crate::moe::hayden::blogi::blog::get_likes::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_at_uri("uri", &self.uri)?;
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::get_series::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_at_uri("uri", &self.uri)?;
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::get_timeline::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        if let Some(algorithm) = &self.algorithm {
+            validate_enum("algorithm", algorithm, &["recent", "popular"])?;
+        }
+        if let Some(window) = &self.window {
+            validate_enum("window", window, &["day", "week", "month", "year"])?;
+        }
+        Ok(())
+    }
+}
+impl Validate for crate::moe::hayden::blogi::blog::search_entries::ParametersData {
+    fn validate(&self) -> core::result::Result<(), String> {
+        validate_length("q", &self.q, 0, 1000)?;
+        Ok(())
+    }
+}
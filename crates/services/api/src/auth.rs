@@ -0,0 +1,245 @@
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use blogi_errors::{BlogiError, Result};
+use blogi_identity::{DidDocument, SigningKey};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{identity::APPVIEW_SERVICE_ID, state::AppState};
+
+/// The account making an authenticated request.
+///
+/// Requests authenticate with an atproto service-auth token: a JWT signed
+/// by the caller's atproto signing key, addressed to this service and bound
+/// to the method being called. Methods that need a viewer take `Viewer` and
+/// respond with `AuthenticationRequired` without one; methods that merely
+/// use one take an `Option<Viewer>`. A token that's present but invalid is
+/// rejected either way.
+pub struct Viewer {
+    pub did: String,
+}
+
+impl Viewer {
+    /// The DID to check what a request can see against, if anyone made it.
+    pub fn did_of(viewer: Option<&Viewer>) -> Option<&str> {
+        viewer.map(|viewer| viewer.did.as_str())
+    }
+}
+
+impl FromRequestParts<AppState> for Viewer {
+    type Rejection = BlogiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        <Viewer as OptionalFromRequestParts<AppState>>::from_request_parts(parts, state)
+            .await?
+            .ok_or(BlogiError::AuthRequired)
+    }
+}
+
+impl OptionalFromRequestParts<AppState> for Viewer {
+    type Rejection = BlogiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Option<Self>> {
+        let Some(authorization) = parts.headers.get(AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .ok_or_else(|| invalid("Expected a bearer token"))?;
+        let method = parts
+            .uri
+            .path()
+            .strip_prefix("/xrpc/")
+            .ok_or_else(|| invalid("Service auth is only accepted by XRPC methods"))?;
+
+        let did = verify(state, token.trim(), method).await?;
+        Ok(Some(Viewer { did }))
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    typ: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iss: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+    lxm: Option<String>,
+}
+
+/// The longest a token may be valid for. Service-auth tokens are minted per
+/// call, so a long-lived one is more likely stolen than needed.
+const MAX_TOKEN_LIFETIME: i64 = 5 * 60;
+/// How far our clock and an issuer's may disagree.
+const CLOCK_SKEW: i64 = 30;
+
+fn invalid(reason: &str) -> BlogiError {
+    BlogiError::InvalidToken(reason.to_string())
+}
+
+/// Checks a service-auth token for `method` and returns the DID that
+/// signed it.
+async fn verify(state: &AppState, token: &str, method: &str) -> Result<String> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(claims), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("Malformed token"));
+    };
+    let message = &token[..header.len() + 1 + claims.len()];
+    let decode = |part: &str| URL_SAFE_NO_PAD.decode(part).map_err(|_| invalid("Malformed token"));
+    let signature = decode(signature)?;
+    let header: Header = serde_json::from_slice(&decode(header)?).map_err(|_| invalid("Malformed token header"))?;
+    let claims: Claims = serde_json::from_slice(&decode(claims)?).map_err(|_| invalid("Malformed token claims"))?;
+
+    // Access, refresh and DPoP tokens are for a PDS, never for us.
+    if matches!(header.typ.as_deref(), Some("at+jwt" | "refresh+jwt" | "dpop+jwt")) {
+        return Err(invalid("Not a service-auth token"));
+    }
+    check_claims(&claims, &state.config.service_did, method, Utc::now().timestamp())?;
+
+    // Services sign as `did#service`, but a viewer is always an account.
+    let did = claims.iss.split('#').next().unwrap_or_default();
+    let key = signing_key(state.did_resolver.resolve(did).await)?;
+    if !verify_signature(&key, &header.alg, message.as_bytes(), &signature)? {
+        // The account may have rotated its key since we cached its document,
+        // unless we've only just fetched it.
+        let Some(document) = state.did_resolver.refresh(did).await.transpose() else {
+            return Err(invalid("Bad token signature"));
+        };
+        let key = signing_key(document)?;
+        if !verify_signature(&key, &header.alg, message.as_bytes(), &signature)? {
+            return Err(invalid("Bad token signature"));
+        }
+    }
+
+    Ok(did.to_string())
+}
+
+/// Checks who a token is for, what it allows and when, as of `now`.
+fn check_claims(claims: &Claims, service_did: &str, method: &str, now: i64) -> Result<()> {
+    // Tokens minted for proxied calls may name our service entry too.
+    let aud = claims.aud.strip_suffix(&format!("#{APPVIEW_SERVICE_ID}")).unwrap_or(&claims.aud);
+    if aud != service_did {
+        return Err(invalid("Token is addressed to another service"));
+    }
+    if claims.exp <= now {
+        return Err(BlogiError::ExpiredToken);
+    }
+    if claims.iat > now + CLOCK_SKEW {
+        return Err(invalid("Token was issued in the future"));
+    }
+    if claims.exp - claims.iat > MAX_TOKEN_LIFETIME || claims.exp - now > MAX_TOKEN_LIFETIME + CLOCK_SKEW {
+        return Err(invalid("Token is valid for too long"));
+    }
+    if claims.lxm.as_deref() != Some(method) {
+        return Err(invalid("Token is for another method"));
+    }
+    Ok(())
+}
+
+fn signing_key(document: Result<DidDocument>) -> Result<SigningKey> {
+    document.and_then(|document| document.signing_key()).map_err(|e| {
+        tracing::debug!("couldn't get a token issuer's signing key: {e}");
+        invalid("Couldn't get the token issuer's signing key")
+    })
+}
+
+fn verify_signature(key: &SigningKey, alg: &str, message: &[u8], signature: &[u8]) -> Result<bool> {
+    use k256::ecdsa::signature::Verifier;
+
+    // atproto only accepts low-S signatures, so high-S ones fail outright
+    // rather than being normalized.
+    Ok(match (key, alg) {
+        (SigningKey::Secp256k1(key), "ES256K") => {
+            let signature = k256::ecdsa::Signature::from_slice(signature).map_err(|_| invalid("Malformed signature"))?;
+            signature.normalize_s().is_none() && key.verify(message, &signature).is_ok()
+        }
+        (SigningKey::P256(key), "ES256") => {
+            let signature = p256::ecdsa::Signature::from_slice(signature).map_err(|_| invalid("Malformed signature"))?;
+            signature.normalize_s().is_none() && key.verify(message, &signature).is_ok()
+        }
+        _ => return Err(invalid("Token algorithm doesn't match the signing key")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{signature::Signer, Signature, SigningKey as PrivateKey};
+
+    use super::*;
+
+    const SERVICE: &str = "did:web:blogi.test";
+    const METHOD: &str = "moe.hayden.blogi.blog.getFollowingFeed";
+    const NOW: i64 = 1_800_000_000;
+
+    fn claims() -> Claims {
+        Claims {
+            iss: "did:plc:abc".to_string(),
+            aud: SERVICE.to_string(),
+            iat: NOW,
+            exp: NOW + 60,
+            lxm: Some(METHOD.to_string()),
+        }
+    }
+
+    fn rejected(claims: Claims) -> bool {
+        check_claims(&claims, SERVICE, METHOD, NOW).is_err()
+    }
+
+    #[test]
+    fn accepts_a_token_for_us_and_the_method() {
+        assert!(check_claims(&claims(), SERVICE, METHOD, NOW).is_ok());
+        let aud = format!("{SERVICE}#{APPVIEW_SERVICE_ID}");
+        assert!(check_claims(&Claims { aud, ..claims() }, SERVICE, METHOD, NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_tokens_for_other_services() {
+        assert!(rejected(Claims { aud: "did:web:other.test".to_string(), ..claims() }));
+        assert!(rejected(Claims { aud: format!("{SERVICE}#atproto_pds"), ..claims() }));
+    }
+
+    #[test]
+    fn rejects_tokens_for_other_methods() {
+        assert!(rejected(Claims { lxm: Some("moe.hayden.blogi.blog.getTimeline".to_string()), ..claims() }));
+        assert!(rejected(Claims { lxm: None, ..claims() }));
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let result = check_claims(&Claims { iat: NOW - 60, exp: NOW, ..claims() }, SERVICE, METHOD, NOW);
+        assert!(matches!(result, Err(BlogiError::ExpiredToken)));
+    }
+
+    #[test]
+    fn rejects_long_lived_and_future_tokens() {
+        assert!(rejected(Claims { exp: NOW + MAX_TOKEN_LIFETIME + 1, ..claims() }));
+        assert!(rejected(Claims { iat: NOW - 3600, exp: NOW + 60, ..claims() }));
+        assert!(rejected(Claims { iat: NOW + 3600, exp: NOW + 3660, ..claims() }));
+        assert!(!rejected(Claims { iat: NOW + CLOCK_SKEW, exp: NOW + CLOCK_SKEW + MAX_TOKEN_LIFETIME, ..claims() }));
+    }
+
+    #[test]
+    fn only_low_s_signatures_verify() {
+        let private = PrivateKey::from_slice(&[7; 32]).unwrap();
+        let key = SigningKey::Secp256k1(*private.verifying_key());
+        let signature: Signature = private.sign(b"message");
+        let (r, s) = signature.split_scalars();
+        let high_s = Signature::from_scalars(r, -s).unwrap();
+
+        assert!(verify_signature(&key, "ES256K", b"message", &signature.to_bytes()).unwrap());
+        assert!(!verify_signature(&key, "ES256K", b"message", &high_s.to_bytes()).unwrap());
+        assert!(!verify_signature(&key, "ES256K", b"other message", &signature.to_bytes()).unwrap());
+        assert!(verify_signature(&key, "ES256", b"message", &signature.to_bytes()).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    io::Cursor,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, Context};
+use atrium_api::types::string::Cid;
+use axum::body::Bytes;
+use blogi_errors::{BlogiError, Result};
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
+use sha2::{Digest, Sha256};
+
+/// The largest blob we're willing to pull from a PDS.
+const MAX_BLOB_SIZE: usize = 5 * 1024 * 1024;
+
+/// Multihash code for SHA2-256, the only hash atproto blob CIDs use.
+const SHA2_256: u64 = 0x12;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Temporary files older than this are left over from a crash rather than
+/// still being written, so eviction can remove them.
+const STALE_TMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// The sizes images are served at. Each preset is a stable part of a blob
+/// URL, so changing what one produces means the cache has to be cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Avatar,
+    Banner,
+    Thumbnail,
+    Fullsize,
+}
+
+impl Preset {
+    fn transform(self, image: DynamicImage) -> DynamicImage {
+        let (width, height) = image.dimensions();
+        match self {
+            Preset::Avatar => image.resize_to_fill(400, 400, FilterType::Lanczos3),
+            Preset::Banner => image.resize_to_fill(1500, 500, FilterType::Lanczos3),
+            Preset::Thumbnail if width > 1000 || height > 1000 => image.resize(1000, 1000, FilterType::Lanczos3),
+            Preset::Fullsize if width > 2000 || height > 2000 => image.resize(2000, 2000, FilterType::Lanczos3),
+            Preset::Thumbnail | Preset::Fullsize => image,
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Preset::Avatar => "avatar",
+            Preset::Banner => "banner",
+            Preset::Thumbnail => "thumbnail",
+            Preset::Fullsize => "fullsize",
+        })
+    }
+}
+
+impl FromStr for Preset {
+    type Err = BlogiError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "avatar" => Ok(Preset::Avatar),
+            "banner" => Ok(Preset::Banner),
+            "thumbnail" => Ok(Preset::Thumbnail),
+            "fullsize" => Ok(Preset::Fullsize),
+            _ => Err(BlogiError::NotFound),
+        }
+    }
+}
+
+/// A processed image, ready to be served.
+pub struct Image {
+    pub bytes: Bytes,
+    pub mime: &'static str,
+}
+
+impl Image {
+    fn from_bytes(bytes: Bytes) -> Image {
+        let mime = match image::guess_format(&bytes) {
+            Ok(ImageFormat::Png) => "image/png",
+            _ => "image/jpeg",
+        };
+        Image { bytes, mime }
+    }
+}
+
+/// Fetches blobs from PDSes and keeps processed copies on disk.
+pub struct BlobStore {
+    http: reqwest::Client,
+    cache: DiskCache,
+}
+
+impl BlobStore {
+    pub async fn open(http: reqwest::Client, dir: &Path, max_size: u64) -> anyhow::Result<BlobStore> {
+        Ok(BlobStore {
+            http,
+            cache: DiskCache::open(dir, max_size).await?,
+        })
+    }
+
+    /// Gets an image blob from the cache, fetching it from `pds` and
+    /// resizing it for `preset` if we haven't seen it before.
+    pub async fn get_image(&self, pds: &str, did: &str, cid: &str, preset: Preset) -> Result<Image> {
+        // Parsing the CID before using it as a cache key keeps arbitrary
+        // paths out of the cache directory.
+        let cid = Cid::from_str(cid).map_err(|_| BlogiError::NotFound)?;
+        let key = format!("{preset}/{}", cid.as_ref());
+        if let Some(bytes) = self.cache.get(&key).await {
+            return Ok(Image::from_bytes(bytes));
+        }
+
+        let raw = self.get_raw(pds, did, &cid).await?;
+        let bytes = tokio::task::spawn_blocking(move || process(&raw, preset))
+            .await
+            .context("image processing panicked")??;
+
+        self.cache.put(&key, &bytes).await?;
+        Ok(Image::from_bytes(bytes))
+    }
+
+    async fn get_raw(&self, pds: &str, did: &str, cid: &Cid) -> Result<Bytes> {
+        let key = format!("raw/{}", cid.as_ref());
+        if let Some(bytes) = self.cache.get(&key).await {
+            return Ok(bytes);
+        }
+
+        let bytes = self.fetch(pds, did, cid).await?;
+        self.cache.put(&key, &bytes).await?;
+        Ok(bytes)
+    }
+
+    async fn fetch(&self, pds: &str, did: &str, cid: &Cid) -> Result<Bytes> {
+        let url = format!("{}/xrpc/com.atproto.sync.getBlob", pds.trim_end_matches('/'));
+        let mut response = self
+            .http
+            .get(url)
+            .query(&[("did", did), ("cid", &cid.as_ref().to_string())])
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| BlogiError::UpstreamFailure(e.into()))?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::BAD_REQUEST => return Err(BlogiError::NotFound),
+            status if !status.is_success() => {
+                return Err(BlogiError::UpstreamFailure(anyhow!("PDS responded to getBlob with {status}")));
+            }
+            _ => {}
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(anyhow::Error::from)? {
+            if bytes.len() + chunk.len() > MAX_BLOB_SIZE {
+                return Err(anyhow!("blob {} is larger than {MAX_BLOB_SIZE} bytes", cid.as_ref()).into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        verify(cid, &bytes)?;
+        Ok(bytes.into())
+    }
+}
+
+/// Checks that blob data matches the CID it was requested by, so a PDS
+/// can't get us to cache something other than what a record points to.
+fn verify(cid: &Cid, bytes: &[u8]) -> anyhow::Result<()> {
+    let hash = cid.as_ref().hash();
+    if hash.code() != SHA2_256 {
+        bail!("unsupported hash function {:#x} in blob CID", hash.code());
+    }
+
+    if hash.digest() != Sha256::digest(bytes).as_slice() {
+        bail!("blob data does not match CID {}", cid.as_ref());
+    }
+
+    Ok(())
+}
+
+fn process(raw: &[u8], preset: Preset) -> anyhow::Result<Bytes> {
+    let image = preset.transform(image::load_from_memory(raw)?);
+
+    // Re-encoding also strips any metadata (e.g. EXIF location) from the original.
+    let mut out = Vec::new();
+    if image.color().has_alpha() {
+        image.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)?;
+    } else {
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+        image.to_rgb8().write_with_encoder(encoder)?;
+    }
+
+    Ok(out.into())
+}
+
+/// A directory of cached files that evicts the oldest entries once it
+/// grows past `max_size` bytes.
+struct DiskCache {
+    dir: PathBuf,
+    max_size: u64,
+    size: AtomicU64,
+    /// Numbers temporary files, so concurrent writers never share one.
+    next_tmp: AtomicU64,
+    /// Keys being written. Keys name their content, so a second writer can
+    /// leave it to the first.
+    writing: Mutex<HashSet<String>>,
+}
+
+impl DiskCache {
+    async fn open(dir: &Path, max_size: u64) -> anyhow::Result<DiskCache> {
+        tokio::fs::create_dir_all(dir).await?;
+
+        let scan_dir = dir.to_path_buf();
+        let size = tokio::task::spawn_blocking(move || scan(&scan_dir))
+            .await??
+            .iter()
+            .map(|(_, len, _)| len)
+            .sum();
+
+        Ok(DiskCache {
+            dir: dir.to_path_buf(),
+            max_size,
+            size: AtomicU64::new(size),
+            next_tmp: AtomicU64::new(0),
+            writing: Mutex::default(),
+        })
+    }
+
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        tokio::fs::read(self.dir.join(key)).await.ok().map(Bytes::from)
+    }
+
+    async fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let Some(_writing) = Writing::start(&self.writing, key) else {
+            return Ok(());
+        };
+        self.write(key, bytes).await
+    }
+
+    async fn write(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let path = self.dir.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temporary file first so readers never see a partial blob.
+        // Other processes may share the directory, so the name includes our PID.
+        let n = self.next_tmp.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_extension(format!("{}.{n}.tmp", std::process::id()));
+        tokio::fs::write(&tmp, bytes).await?;
+
+        // Overwriting a key replaces its old size rather than adding to it.
+        let replaced = tokio::fs::metadata(&path).await.map_or(0, |metadata| metadata.len());
+        tokio::fs::rename(&tmp, &path).await?;
+
+        let grow = |size: u64| (size + bytes.len() as u64).saturating_sub(replaced);
+        let previous = self
+            .size
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| Some(grow(size)))
+            .unwrap_or_else(|size| size);
+        if grow(previous) > self.max_size {
+            self.evict().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the oldest files until the cache is back under 90% of its
+    /// limit. Temporary files are skipped while they might still be written.
+    async fn evict(&self) -> anyhow::Result<()> {
+        let dir = self.dir.clone();
+        let target = self.max_size / 10 * 9;
+        let size = tokio::task::spawn_blocking(move || -> anyhow::Result<u64> {
+            let mut files = scan(&dir)?;
+            files.sort_by_key(|(_, _, modified)| *modified);
+
+            let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+            for (path, len, modified) in files {
+                if size <= target {
+                    break;
+                }
+                let in_progress = path.extension().is_some_and(|ext| ext == "tmp")
+                    && modified.elapsed().is_ok_and(|age| age < STALE_TMP_AGE);
+                if in_progress {
+                    continue;
+                }
+                if std::fs::remove_file(&path).is_ok() {
+                    size -= len;
+                }
+            }
+            Ok(size)
+        })
+        .await??;
+
+        self.size.store(size, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Marks a key as being written until dropped, even if the write is cancelled.
+struct Writing<'a> {
+    keys: &'a Mutex<HashSet<String>>,
+    key: String,
+}
+
+impl<'a> Writing<'a> {
+    fn start(keys: &'a Mutex<HashSet<String>>, key: &str) -> Option<Writing<'a>> {
+        keys.lock().unwrap().insert(key.to_string()).then(|| Writing {
+            keys,
+            key: key.to_string(),
+        })
+    }
+}
+
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        self.keys.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Lists every file under `dir` with its size and modification time.
+fn scan(dir: &Path) -> anyhow::Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            files.extend(scan(&entry.path())?);
+        } else {
+            files.push((entry.path(), metadata.len(), metadata.modified()?));
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrent_puts_of_a_key_each_succeed_and_count_once() {
+        let dir = std::env::temp_dir().join(format!("blogi-disk-cache-{}", std::process::id()));
+        let cache = DiskCache::open(&dir, u64::MAX).await.unwrap();
+
+        let (a, b, c) = tokio::join!(
+            cache.put("raw/key", &[1; 100]),
+            cache.put("raw/key", &[2; 100]),
+            cache.put("raw/key", &[3; 100]),
+        );
+        a.unwrap();
+        b.unwrap();
+        c.unwrap();
+
+        assert_eq!(cache.size.load(Ordering::Relaxed), 100);
+        assert_eq!(cache.get("raw/key").await.unwrap().len(), 100);
+        assert_eq!(scan(&dir).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use blogi_errors::{BlogiError, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// How deep into a thread replies are fetched.
+const THREAD_DEPTH: u16 = 10;
+
+/// How long a thread is served from memory before it's fetched again.
+const THREAD_TTL: Duration = Duration::from_secs(60);
+
+/// How long to stop asking an AppView that timed out or errored, so pages
+/// don't each wait on it while it's down.
+const FAILURE_TTL: Duration = Duration::from_secs(30);
+
+/// A reply to a Bluesky post, flattened out of its thread.
+#[derive(Clone)]
+pub struct Reply {
+    pub uri: String,
+    pub cid: String,
+    pub author: ReplyAuthor,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// The reply this one responds to, unless it's a direct reply to the
+    /// thread's root.
+    pub parent: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplyAuthor {
+    pub did: String,
+    pub handle: String,
+    pub display_name: Option<String>,
+    /// The avatar on the AppView's CDN.
+    pub avatar: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GetPostThreadOutput {
+    thread: ThreadNode,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "$type")]
+enum ThreadNode {
+    #[serde(rename = "app.bsky.feed.defs#threadViewPost")]
+    Post(Box<ThreadViewPost>),
+    /// Deleted, blocked or otherwise unavailable posts.
+    #[serde(other)]
+    Unavailable,
+}
+
+#[derive(Deserialize)]
+struct ThreadViewPost {
+    post: PostView,
+    #[serde(default)]
+    replies: Vec<ThreadNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostView {
+    uri: String,
+    cid: String,
+    author: ReplyAuthor,
+    record: PostRecord,
+    indexed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostRecord {
+    #[serde(default)]
+    text: String,
+    created_at: Option<String>,
+}
+
+/// Reads Bluesky threads through an AppView.
+pub struct BskyClient {
+    http: reqwest::Client,
+    appview_url: String,
+    threads: moka::future::Cache<String, Arc<Vec<Reply>>>,
+    /// Set while the AppView is being left alone after a failure.
+    unavailable_until: Mutex<Option<Instant>>,
+}
+
+impl BskyClient {
+    pub fn new(http: reqwest::Client, appview_url: &str) -> BskyClient {
+        BskyClient {
+            http,
+            appview_url: appview_url.trim_end_matches('/').to_string(),
+            threads: moka::future::Cache::builder()
+                .time_to_live(THREAD_TTL)
+                .max_capacity(10_000)
+                .build(),
+            unavailable_until: Mutex::default(),
+        }
+    }
+
+    /// Lists the replies to a post, oldest first.
+    pub async fn replies(&self, uri: &str) -> Result<Arc<Vec<Reply>>> {
+        if let Some(replies) = self.threads.get(uri).await {
+            return Ok(replies);
+        }
+        if self.unavailable_until.lock().unwrap().is_some_and(|until| Instant::now() < until) {
+            return Err(BlogiError::UpstreamFailure(anyhow!("AppView failed recently; not retrying yet")));
+        }
+
+        let response = self
+            .http
+            .get(format!("{}/xrpc/app.bsky.feed.getPostThread", self.appview_url))
+            .query(&[("uri", uri), ("depth", &THREAD_DEPTH.to_string()), ("parentHeight", "0")])
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| self.failed(e.into()))?;
+        let status = response.status();
+        if status.is_server_error() {
+            return Err(self.failed(anyhow!("AppView responded to getPostThread with {status}")));
+        }
+        if !status.is_success() {
+            return Err(BlogiError::UpstreamFailure(anyhow!("AppView responded to getPostThread with {status}")));
+        }
+        let output: GetPostThreadOutput = response.json().await.map_err(|e| anyhow!(e))?;
+
+        let mut replies = Vec::new();
+        if let ThreadNode::Post(root) = output.thread {
+            flatten(root.replies, None, &mut replies);
+        }
+        replies.sort_by_key(|reply| reply.created_at);
+
+        let replies = Arc::new(replies);
+        self.threads.insert(uri.to_string(), replies.clone()).await;
+        Ok(replies)
+    }
+
+    /// Backs off from an AppView that's down, rather than a thread that
+    /// doesn't exist.
+    fn failed(&self, e: anyhow::Error) -> BlogiError {
+        *self.unavailable_until.lock().unwrap() = Some(Instant::now() + FAILURE_TTL);
+        BlogiError::UpstreamFailure(e)
+    }
+}
+
+fn flatten(nodes: Vec<ThreadNode>, parent: Option<&str>, out: &mut Vec<Reply>) {
+    for node in nodes {
+        let ThreadNode::Post(node) = node else {
+            continue;
+        };
+
+        // Record timestamps are client-supplied, so fall back to when the
+        // AppView saw the post.
+        let created_at = node
+            .post
+            .record
+            .created_at
+            .as_deref()
+            .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+            .map_or(node.post.indexed_at, |created_at| created_at.to_utc());
+
+        out.push(Reply {
+            uri: node.post.uri.clone(),
+            cid: node.post.cid,
+            author: node.post.author,
+            text: node.post.record.text,
+            created_at,
+            parent: parent.map(str::to_string),
+        });
+        flatten(node.replies, Some(&node.post.uri), out);
+    }
+}
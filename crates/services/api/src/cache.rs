@@ -0,0 +1,236 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{MatchedPath, Request},
+    http::{
+        header::{self, HeaderName},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::session::SESSION_COOKIE;
+
+/// Pages and XRPC reads change whenever an author publishes or a reader
+/// comments, so shared caches only hold them briefly, but may keep serving
+/// a stale copy while they revalidate it.
+const READ: &str = "public, max-age=60, stale-while-revalidate=600";
+/// Feed readers poll, so there's no point revalidating on every poll.
+const FEED: &str = "public, max-age=300";
+/// Documents about the service itself only change on a redeploy.
+const SERVICE: &str = "public, max-age=3600";
+/// A read that depended on who was asking.
+const PERSONAL: &str = "private, no-cache";
+const NO_STORE: &str = "no-store";
+
+/// The headers a 304 repeats from the response it stands in for.
+const NOT_MODIFIED_HEADERS: [HeaderName; 4] =
+    [header::CACHE_CONTROL, header::ETAG, header::LAST_MODIFIED, header::VARY];
+
+/// XRPC callers authenticate with a header, so reads only differ by it.
+const BY_TOKEN: &str = "Authorization";
+/// Pages show who's signed in, which browsers say with the session cookie.
+const BY_SESSION: &str = "Authorization, Cookie";
+
+/// How a route's responses may be cached: their `Cache-Control`, and the
+/// request headers besides the URL that they `Vary` on.
+#[derive(Debug, PartialEq)]
+struct Policy {
+    cache_control: &'static str,
+    vary: &'static str,
+}
+
+/// The policy for a route, by its path pattern. Routes without one aren't
+/// cached at all.
+fn policy(route: &str) -> Option<Policy> {
+    let (cache_control, vary) = match route {
+        "/xrpc/_health" => return None,
+        "/.well-known/did.json" | "/oauth/client-metadata.json" => (SERVICE, BY_TOKEN),
+        // Feeds are read without cookies, and only ever list what's public.
+        "/tags/{tag}/atom.xml" | "/{actor}/tags/{tag}/atom.xml" => (FEED, BY_TOKEN),
+        "/oembed" => (READ, BY_TOKEN),
+        "/tags/{tag}" | "/{actor}/tags/{tag}" => (READ, BY_SESSION),
+        "/{actor}/{rkey}" | "/{actor}/{rkey}/diff" | "/{actor}/{rkey}/revisions" => (READ, BY_SESSION),
+        route if route.starts_with("/xrpc/") => (READ, BY_TOKEN),
+        _ => return None,
+    };
+    Some(Policy { cache_control, vary })
+}
+
+/// Gives successful reads a `Cache-Control` policy and an ETag, and answers
+/// conditional requests with 304 Not Modified when the client's copy is
+/// still current.
+///
+/// Handlers that know a cheaper validator, like the CID of the record they
+/// serve, set `ETag` or `Last-Modified` themselves; anything else is tagged
+/// with a digest of its body.
+pub async fn layer(request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+
+    let policy = request.extensions().get::<MatchedPath>().and_then(|route| policy(route.as_str()));
+    let personal = request.headers().contains_key(header::AUTHORIZATION)
+        || CookieJar::from_headers(request.headers()).get(SESSION_COOKIE).is_some();
+    let conditions = request.headers().clone();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    if !parts.headers.contains_key(header::CACHE_CONTROL) {
+        let Some(policy) = policy else {
+            parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(NO_STORE));
+            return Response::from_parts(parts, body);
+        };
+        let cache_control = if personal { PERSONAL } else { policy.cache_control };
+        parts.headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+        // Signed-in readers' copies are private, but the anonymous copy is
+        // public, so shared caches must not hand it to someone signed in.
+        parts.headers.insert(header::VARY, HeaderValue::from_static(policy.vary));
+    }
+
+    let body = if parts.headers.contains_key(header::ETAG) {
+        body
+    } else {
+        let bytes = match to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("couldn't buffer a response to tag it: {e}");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        if let Ok(etag) = HeaderValue::try_from(etag(&bytes)) {
+            parts.headers.insert(header::ETAG, etag);
+        }
+        Body::from(bytes)
+    };
+
+    if !not_modified(&conditions, &parts.headers) {
+        return Response::from_parts(parts, body);
+    }
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    for name in NOT_MODIFIED_HEADERS {
+        if let Some(value) = parts.headers.remove(&name) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+/// A strong ETag for a response body.
+fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", URL_SAFE_NO_PAD.encode(&digest[..16]))
+}
+
+/// Builds a weak ETag from the versions of what a response shows, like the
+/// CIDs of its records, so handlers can tag responses without the body
+/// being buffered and hashed.
+#[derive(Default)]
+pub struct Validator(Sha256);
+
+impl Validator {
+    pub fn add(&mut self, part: impl AsRef<[u8]>) -> &mut Self {
+        self.0.update(part.as_ref());
+        // Keeps ("ab", "c") and ("a", "bc") apart.
+        self.0.update([0]);
+        self
+    }
+
+    pub fn etag(self) -> String {
+        format!("W/\"{}\"", URL_SAFE_NO_PAD.encode(&self.0.finalize()[..16]))
+    }
+}
+
+/// Formats a time as an HTTP date, for `Last-Modified`.
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Whether a request's conditions say the client already has `response`.
+fn not_modified(request: &HeaderMap, response: &HeaderMap) -> bool {
+    // If-None-Match wins when both are sent, since ETags are more precise.
+    if let Some(if_none_match) = header_str(request, &header::IF_NONE_MATCH) {
+        let Some(etag) = header_str(response, &header::ETAG) else {
+            return false;
+        };
+        return if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| opaque_tag(tag) == opaque_tag(etag));
+    }
+
+    let parse = |date: &str| DateTime::parse_from_rfc2822(date).ok();
+    match (
+        header_str(request, &header::IF_MODIFIED_SINCE).and_then(parse),
+        header_str(response, &header::LAST_MODIFIED).and_then(parse),
+    ) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// GETs compare ETags weakly, so a `W/` prefix doesn't matter.
+fn opaque_tag(tag: &str) -> &str {
+    tag.trim().trim_start_matches("W/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn pages_vary_on_the_session_cookie() {
+        for route in ["/{actor}/{rkey}", "/{actor}/{rkey}/revisions", "/tags/{tag}", "/{actor}/tags/{tag}"] {
+            assert_eq!(policy(route), Some(Policy { cache_control: READ, vary: BY_SESSION }), "{route}");
+        }
+        assert_eq!(policy("/xrpc/moe.hayden.blogi.blog.getEntry"), Some(Policy { cache_control: READ, vary: BY_TOKEN }));
+        assert_eq!(policy("/tags/{tag}/atom.xml").map(|policy| policy.cache_control), Some(FEED));
+        assert_eq!(policy("/xrpc/_health"), None);
+        assert_eq!(policy("/editor/{rkey}"), None);
+    }
+
+    #[test]
+    fn if_none_match_compares_etags_weakly() {
+        let response = headers(&[(header::ETAG, "W/\"abc\"")]);
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, "\"abc\"")]), &response));
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, "\"xyz\", W/\"abc\"")]), &response));
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, "*")]), &response));
+        assert!(!not_modified(&headers(&[(header::IF_NONE_MATCH, "\"xyz\"")]), &response));
+        assert!(!not_modified(&headers(&[(header::IF_NONE_MATCH, "\"abc\"")]), &HeaderMap::new()));
+    }
+
+    #[test]
+    fn if_modified_since_compares_dates_unless_an_etag_was_asked_for() {
+        let response = headers(&[
+            (header::ETAG, "\"abc\""),
+            (header::LAST_MODIFIED, "Mon, 19 Oct 2026 06:00:00 GMT"),
+        ]);
+        let since = |date| headers(&[(header::IF_MODIFIED_SINCE, date)]);
+        assert!(not_modified(&since("Mon, 19 Oct 2026 06:00:00 GMT"), &response));
+        assert!(not_modified(&since("Mon, 19 Oct 2026 07:00:00 GMT"), &response));
+        assert!(!not_modified(&since("Mon, 19 Oct 2026 05:00:00 GMT"), &response));
+        assert!(!not_modified(&since("not a date"), &response));
+
+        let both = headers(&[
+            (header::IF_NONE_MATCH, "\"xyz\""),
+            (header::IF_MODIFIED_SINCE, "Mon, 19 Oct 2026 07:00:00 GMT"),
+        ]);
+        assert!(!not_modified(&both, &response));
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::blobs::Preset;
+
+/// What has to be escaped in a URL path segment.
+const PATH_SEGMENT: &AsciiSet =
+    &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'/').add(b'<').add(b'>').add(b'?').add(b'`').add(b'{').add(b'}');
+
+/// Runtime configuration for the API service.
+#[derive(Clone)]
+pub struct ApiConfig {
+    /// The public URL readers reach this service at, without a trailing
+    /// slash. Used to build absolute links in rendered pages.
+    pub public_url: String,
+
+    /// Where proxied blobs are cached on disk.
+    pub blob_cache_dir: PathBuf,
+
+    /// How large the blob cache may grow, in bytes.
+    pub blob_cache_max_size: u64,
+
+    /// The AppView replies to entries' Bluesky announcements are read from.
+    pub bsky_appview_url: String,
+
+    /// This service's own DID. Service-auth tokens must be addressed to it.
+    pub service_did: String,
+
+    /// The hex-encoded secp256k1 key published in this service's DID
+    /// document. One is derived from `secret_key` if it isn't set.
+    pub signing_key: Option<String>,
+
+    /// The PLC directory `did:plc` identities are resolved through.
+    pub plc_directory_url: String,
+
+    /// Encrypts sign-in sessions at rest. Changing it signs everyone out.
+    pub secret_key: String,
+
+    /// The Redis instance sign-in sessions are kept in.
+    pub redis_url: String,
+}
+
+impl ApiConfig {
+    pub fn new(public_url: &str) -> ApiConfig {
+        ApiConfig {
+            public_url: public_url.trim_end_matches('/').to_string(),
+            blob_cache_dir: PathBuf::from("blobs"),
+            blob_cache_max_size: 1024 * 1024 * 1024,
+            bsky_appview_url: "https://public.api.bsky.app".to_string(),
+            service_did: "did:web:localhost".to_string(),
+            signing_key: None,
+            plc_directory_url: "https://plc.directory".to_string(),
+            secret_key: String::new(),
+            redis_url: "redis://localhost:6379/0".to_string(),
+        }
+    }
+
+    /// Builds an absolute URL for a path on this service.
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}/{}", self.public_url, path.trim_start_matches('/'))
+    }
+
+    /// Whether readers reach this service over HTTPS, so cookies can be
+    /// marked secure.
+    pub fn is_https(&self) -> bool {
+        self.public_url.starts_with("https://")
+    }
+
+    /// Builds the canonical URL of a rendered post page.
+    pub fn post_url(&self, did: &str, rkey: &str) -> String {
+        self.url_for(&format!("{did}/{rkey}"))
+    }
+
+    /// Builds the URL of the posts tagged `tag`, by one author or by everyone.
+    pub fn tag_url(&self, did: Option<&str>, tag: &str) -> String {
+        let tag = utf8_percent_encode(tag, PATH_SEGMENT);
+        match did {
+            Some(did) => self.url_for(&format!("{did}/tags/{tag}")),
+            None => self.url_for(&format!("tags/{tag}")),
+        }
+    }
+
+    /// Builds the URL of the generated social preview image for a post.
+    pub fn og_image_url(&self, did: &str, rkey: &str) -> String {
+        self.url_for(&format!("og/{did}/{rkey}.png"))
+    }
+
+    /// Builds the stable URL a blob is served at through the blob proxy.
+    pub fn blob_url(&self, preset: Preset, did: &str, cid: &str) -> String {
+        self.url_for(&format!("blob/{preset}/{did}/{cid}"))
+    }
+}
@@ -0,0 +1,14 @@
+use blogi_errors::{BlogiError, Result};
+use blogi_lexicons::moe::hayden::blogi::actor::get_profile;
+
+use crate::{state::AppState, views};
+
+pub async fn get_profile(
+    AppState { db, config, .. }: &AppState,
+    params: get_profile::Parameters,
+) -> Result<get_profile::Output> {
+    let actor = db.get_actor(params.actor.as_ref()).await?.ok_or(BlogiError::NotFound)?;
+    let counts = db.get_follow_counts(&actor.did).await?;
+
+    views::profile_view_detailed(config, &actor, &counts)
+}
@@ -0,0 +1,27 @@
+use axum::{extract::{Path, State}, http::header, response::IntoResponse};
+use blogi_errors::{BlogiError, Result};
+
+use crate::{blobs::Preset, state::AppState};
+
+pub async fn get_blob(
+    State(AppState { db, blobs, .. }): State<AppState>,
+    Path((preset, did, cid)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse> {
+    let preset: Preset = preset.parse()?;
+
+    // Only proxy for accounts we index, so this isn't an open relay.
+    let actor = db.get_actor(&did).await?.ok_or(BlogiError::NotFound)?;
+    let pds = actor.pds_endpoint.as_deref().ok_or(BlogiError::NotFound)?;
+
+    let image = blobs.get_image(pds, &actor.did, &cid, preset).await?;
+
+    // Blob URLs are content-addressed, so they never change.
+    Ok((
+        [
+            (header::CONTENT_TYPE, image.mime.to_string()),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable".to_string()),
+            (header::ETAG, format!("\"{cid}\"")),
+        ],
+        image.bytes,
+    ))
+}
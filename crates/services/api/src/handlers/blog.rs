@@ -0,0 +1,239 @@
+use blogi_errors::{BlogiError, Result};
+use chrono::{TimeDelta, Utc};
+use blogi_lexicons::moe::hayden::blogi::blog::{
+    defs::SearchResultData, get_comments, get_entries_by_tag, get_entries_for_author, get_entry_revisions,
+    get_following_feed, get_likes, get_series, get_timeline, search_entries,
+};
+
+use crate::{auth::Viewer, pagination, render::highlight_snippet, state::AppState, views};
+
+pub async fn get_comments(
+    AppState { db, config, bsky, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_comments::Parameters,
+) -> Result<get_comments::Output> {
+    let entry = db
+        .get_entry(views::did(&params.uri), views::rkey(&params.uri), Viewer::did_of(viewer.as_ref()))
+        .await?
+        .filter(|entry| entry.uri == params.uri)
+        .ok_or(BlogiError::NotFound)?;
+
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let page = views::comment_views(db.as_ref().as_ref(), config, bsky, &entry, limit, cursor.as_ref()).await?;
+
+    Ok(get_comments::OutputData {
+        comments: page.comments,
+        cursor: page.cursor,
+    }
+    .into())
+}
+
+pub async fn get_entries_by_tag(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_entries_by_tag::Parameters,
+) -> Result<get_entries_by_tag::Output> {
+    let did = match &params.actor {
+        Some(actor) => Some(db.get_actor(actor.as_ref()).await?.ok_or(BlogiError::NotFound)?.did),
+        None => None,
+    };
+
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let entries = db
+        .list_entries_by_tag(&params.tag, did.as_deref(), Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref())
+        .await?;
+
+    Ok(get_entries_by_tag::OutputData {
+        cursor: pagination::next_cursor(config, &entries, limit),
+        posts: views::post_views(db.as_ref().as_ref(), config, viewer.as_ref(), &entries).await?,
+    }
+    .into())
+}
+
+pub async fn get_entries_for_author(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_entries_for_author::Parameters,
+) -> Result<get_entries_for_author::Output> {
+    let author = db.get_actor(params.author_did.as_ref()).await?.ok_or(BlogiError::NotFound)?;
+
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let entries = db
+        .list_entries_for_author(&author.did, Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref())
+        .await?;
+
+    Ok(get_entries_for_author::OutputData {
+        cursor: pagination::next_cursor(config, &entries, limit),
+        posts: views::post_views(db.as_ref().as_ref(), config, viewer.as_ref(), &entries).await?,
+    }
+    .into())
+}
+
+pub async fn get_entry_revisions(
+    AppState { db, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_entry_revisions::Parameters,
+) -> Result<get_entry_revisions::Output> {
+    let entry = db
+        .get_entry(views::did(&params.uri), views::rkey(&params.uri), Viewer::did_of(viewer.as_ref()))
+        .await?
+        .filter(|entry| entry.uri == params.uri)
+        .ok_or(BlogiError::NotFound)?;
+
+    let revisions = db.list_entry_revisions(&entry.uri, Viewer::did_of(viewer.as_ref())).await?;
+
+    Ok(get_entry_revisions::OutputData {
+        revisions: revisions.iter().map(views::revision_view).collect::<Result<_>>()?,
+        uri: entry.uri,
+    }
+    .into())
+}
+
+pub async fn get_following_feed(
+    AppState { db, config, .. }: &AppState,
+    viewer: Viewer,
+    params: get_following_feed::Parameters,
+) -> Result<get_following_feed::Output> {
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let entries = db
+        .list_following_entries(&viewer.did, limit as i64, cursor.as_ref())
+        .await?;
+
+    Ok(get_following_feed::OutputData {
+        cursor: pagination::next_cursor(config, &entries, limit),
+        posts: views::post_views(db.as_ref().as_ref(), config, Some(&viewer), &entries).await?,
+    }
+    .into())
+}
+
+pub async fn get_series(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_series::Parameters,
+) -> Result<get_series::Output> {
+    let series = db.get_series(&params.uri).await?.ok_or(BlogiError::NotFound)?;
+    let author = db.get_actor(&series.did).await?.ok_or(BlogiError::NotFound)?;
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let entries = db
+        .list_series_entries(&series, Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref())
+        .await?;
+    let entry_count = db.count_series_entries(&series, Viewer::did_of(viewer.as_ref())).await?;
+    let mut posts = entries
+        .iter()
+        .map(|entry| views::post_view(config, entry, &author))
+        .collect::<Result<Vec<_>>>()?;
+    views::apply_viewer_state(db.as_ref().as_ref(), viewer.as_ref(), &mut posts).await?;
+
+    Ok(get_series::OutputData {
+        cursor: pagination::next_series_cursor(config, &series, &entries, limit),
+        series: views::series_view(config, &series, &author, entry_count)?,
+        posts,
+    }
+    .into())
+}
+
+pub async fn get_timeline(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_timeline::Parameters,
+) -> Result<get_timeline::Output> {
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+
+    // The lexicon restricts both parameters to the values matched here.
+    let (entries, cursor) = if params.algorithm.as_deref() == Some("popular") {
+        let window = match params.window.as_deref() {
+            Some("day") => TimeDelta::days(1),
+            Some("month") => TimeDelta::days(30),
+            Some("year") => TimeDelta::days(365),
+            _ => TimeDelta::weeks(1),
+        };
+        let entries = db
+            .list_popular_entries(Utc::now() - window, Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref())
+            .await?;
+        let cursor = pagination::next_popular_cursor(config, &entries, limit);
+        (entries, cursor)
+    } else {
+        let entries = db.list_timeline(Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref()).await?;
+        let cursor = pagination::next_cursor(config, &entries, limit);
+        (entries, cursor)
+    };
+
+    Ok(get_timeline::OutputData {
+        cursor,
+        posts: views::post_views(db.as_ref().as_ref(), config, viewer.as_ref(), &entries).await?,
+    }
+    .into())
+}
+
+pub async fn search_entries(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: search_entries::Parameters,
+) -> Result<search_entries::Output> {
+    let did = match &params.actor {
+        Some(actor) => Some(db.get_actor(actor.as_ref()).await?.ok_or(BlogiError::NotFound)?.did),
+        None => None,
+    };
+
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let results = db
+        .search_entries(&params.q, did.as_deref(), Viewer::did_of(viewer.as_ref()), limit as i64, cursor.as_ref())
+        .await?;
+
+    let cursor = pagination::next_search_cursor(config, &results, limit);
+    let dids = results.iter().map(|result| result.entry.did.as_str());
+    let authors = views::authors(db.as_ref().as_ref(), dids).await?;
+    let mut results = results
+        .iter()
+        .filter_map(|result| {
+            let author = authors.get(&result.entry.did)?;
+            Some(views::post_view(config, &result.entry, author).map(|post| {
+                SearchResultData { post, snippet: highlight_snippet(&result.snippet) }
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    views::apply_viewer_state(db.as_ref().as_ref(), viewer.as_ref(), results.iter_mut().map(|result| &mut result.post))
+        .await?;
+
+    Ok(search_entries::OutputData {
+        cursor,
+        results: results.into_iter().map(Into::into).collect(),
+    }
+    .into())
+}
+
+pub async fn get_likes(
+    AppState { db, config, .. }: &AppState,
+    viewer: Option<Viewer>,
+    params: get_likes::Parameters,
+) -> Result<get_likes::Output> {
+    let entry = db
+        .get_entry(views::did(&params.uri), views::rkey(&params.uri), Viewer::did_of(viewer.as_ref()))
+        .await?
+        .filter(|entry| entry.uri == params.uri)
+        .ok_or(BlogiError::NotFound)?;
+
+    let limit = pagination::limit(params.limit);
+    let cursor = pagination::cursor(config, params.cursor.as_deref())?;
+    let likes = db.list_likes(&entry.uri, limit as i64, cursor.as_ref()).await?;
+
+    let actors = views::authors(db.as_ref().as_ref(), likes.iter().map(|like| like.did.as_str())).await?;
+    let views = likes
+        .iter()
+        .map(|like| views::like_view(config, like, actors.get(&like.did)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(get_likes::OutputData {
+        cursor: pagination::next_like_cursor(config, &likes, limit),
+        likes: views,
+        uri: params.data.uri,
+    }
+    .into())
+}
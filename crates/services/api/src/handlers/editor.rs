@@ -0,0 +1,355 @@
+use anyhow::anyhow;
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use blogi_db::{
+    entry::EntryImage,
+    records::{self, ENTRY_COLLECTION},
+};
+use blogi_errors::{BlogiError, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    oauth::XrpcError,
+    pagination,
+    render::{content_to_html, ContentFormat},
+    session::{session_key, Session, SESSION_TTL},
+    state::AppState,
+    templates::{render, AccountNav, EditorEntry, EditorListPage, EditorPage},
+    views,
+};
+
+/// The lexicon's limits on entries. Lengths are in bytes, and graphemes are
+/// what readers count as characters.
+const MAX_TITLE_LENGTH: usize = 1000;
+const MAX_CONTENT_LENGTH: usize = 100_000;
+const MAX_TAGS: usize = 8;
+const MAX_TAG_LENGTH: usize = 640;
+const MAX_TAG_GRAPHEMES: usize = 64;
+
+const CONFLICT: &str = "This entry was changed somewhere else since you opened it. \
+    Copy your changes, then open it again to edit the latest version.";
+
+fn sign_in(return_to: &str) -> Response {
+    let return_to = url::form_urlencoded::byte_serialize(return_to.as_bytes()).collect::<String>();
+    Redirect::to(&format!("/login?return_to={return_to}")).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct EntriesParams {
+    cursor: Option<String>,
+}
+
+/// Lists the signed-in author's entries, drafts included.
+pub async fn entries_page(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Query(params): Query<EntriesParams>,
+) -> Result<Response> {
+    let Some(session) = session else {
+        return Ok(sign_in("/editor"));
+    };
+
+    let limit = pagination::limit(None);
+    let cursor = pagination::cursor(&state.config, params.cursor.as_deref())?;
+    let entries = state
+        .db
+        .list_entries_for_author(&session.did, Some(&session.did), limit as i64, cursor.as_ref())
+        .await?;
+
+    Ok(render(&EditorListPage {
+        account: AccountNav::new(Some(&session), "/editor"),
+        entries: entries.iter().map(|entry| EditorEntry::new(&state.config, entry)).collect(),
+        next_url: pagination::next_cursor(&state.config, &entries, limit).map(|cursor| {
+            let cursor = url::form_urlencoded::byte_serialize(cursor.as_bytes()).collect::<String>();
+            format!("/editor?cursor={cursor}")
+        }),
+    })?
+    .into_response())
+}
+
+pub async fn new_entry_page(session: Option<Session>) -> Result<Response> {
+    let Some(session) = session else {
+        return Ok(sign_in("/editor/new"));
+    };
+
+    Ok(render(&EditorPage::new(&session, "/editor/new"))?.into_response())
+}
+
+#[derive(Deserialize)]
+pub struct EditEntryParams {
+    saved: Option<String>,
+}
+
+pub async fn edit_entry_page(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Path(rkey): Path<String>,
+    Query(params): Query<EditEntryParams>,
+) -> Result<Response> {
+    let path = format!("/editor/{rkey}");
+    let Some(mut session) = session else {
+        return Ok(sign_in(&path));
+    };
+
+    let record = get_record(&state, &mut session, &rkey).await?;
+    let (record, cid) = record.ok_or(BlogiError::NotFound)?;
+
+    let field = |name: &str| record[name].as_str().unwrap_or_default().to_string();
+    let page = EditorPage {
+        title: field("title"),
+        content: field("content"),
+        content_format: ContentFormat::from_mime(record["contentFormat"].as_str()).mime().to_string(),
+        tags: record["tags"]
+            .as_array()
+            .map(|tags| tags.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default(),
+        unlisted: record["visibility"] == "unlisted",
+        live: record["status"] != "draft",
+        swap_cid: Some(cid),
+        view_url: Some(state.config.post_url(&session.did, &rkey)),
+        notice: params.saved.is_some().then_some("Saved."),
+        ..EditorPage::new(&session, &path)
+    };
+    Ok(render(&page)?.into_response())
+}
+
+#[derive(Deserialize)]
+pub struct EntryForm {
+    title: String,
+    content: String,
+    content_format: Option<String>,
+    tags: String,
+    visibility: Option<String>,
+    swap_cid: Option<String>,
+    /// Which button was pressed: `preview`, `draft` or `publish`.
+    action: String,
+}
+
+pub async fn save_new_entry(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Form(form): Form<EntryForm>,
+) -> Result<Response> {
+    save(state, session, None, form).await
+}
+
+pub async fn save_entry(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Path(rkey): Path<String>,
+    Form(form): Form<EntryForm>,
+) -> Result<Response> {
+    save(state, session, Some(rkey), form).await
+}
+
+/// Previews an entry, or writes it to the author's repo and indexes it
+/// straight away so it shows before the firehose catches up.
+async fn save(state: AppState, session: Option<Session>, rkey: Option<String>, form: EntryForm) -> Result<Response> {
+    let path = match &rkey {
+        Some(rkey) => format!("/editor/{rkey}"),
+        None => "/editor/new".to_string(),
+    };
+    let Some(mut session) = session else {
+        return Ok(sign_in(&path));
+    };
+
+    let format = ContentFormat::from_mime(form.content_format.as_deref());
+    let tags: Vec<String> = form
+        .tags
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let indexed = match &rkey {
+        Some(rkey) => state.db.get_entry(&session.did, rkey, Some(&session.did)).await?,
+        None => None,
+    };
+
+    let mut page = EditorPage {
+        title: form.title.trim().to_string(),
+        content: form.content.clone(),
+        content_format: format.mime().to_string(),
+        tags: tags.join(", "),
+        unlisted: form.visibility.as_deref() == Some("unlisted"),
+        live: indexed.as_ref().is_some_and(|entry| entry.status.as_deref() != Some("draft")),
+        swap_cid: form.swap_cid.clone(),
+        view_url: rkey.as_ref().map(|rkey| state.config.post_url(&session.did, rkey)),
+        ..EditorPage::new(&session, &path)
+    };
+
+    page.error = validate(&page.title, &page.content, &tags);
+    if page.error.is_some() {
+        return Ok(render(&page)?.into_response());
+    }
+
+    let images: Vec<EntryImage> = indexed.map(|entry| entry.images.0).unwrap_or_default();
+    if form.action == "preview" {
+        let images = images
+            .iter()
+            .map(|image| views::image_view(&state.config, &session.did, image))
+            .collect::<Result<Vec<_>>>()?;
+        page.preview = Some(content_to_html(&page.content, format, &images));
+        return Ok(render(&page)?.into_response());
+    }
+
+    // Start from the current record so fields the editor doesn't know about
+    // survive, and refuse to save over changes made elsewhere.
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    let (mut record, swap_cid) = match &rkey {
+        Some(rkey) => {
+            let current = get_record(&state, &mut session, rkey).await?;
+            let (record, cid) = current.ok_or(BlogiError::NotFound)?;
+            if form.swap_cid.as_deref() != Some(cid.as_str()) {
+                page.error = Some(CONFLICT.to_string());
+                return Ok(render(&page)?.into_response());
+            }
+            (record, Some(cid))
+        }
+        None => (json!({ "$type": ENTRY_COLLECTION, "createdAt": now }), None),
+    };
+    record["title"] = page.title.clone().into();
+    record["content"] = page.content.clone().into();
+    record["contentFormat"] = format.mime().into();
+    record["status"] = if form.action == "publish" { "live" } else { "draft" }.into();
+    record["visibility"] = if page.unlisted { "unlisted" } else { "public" }.into();
+    if swap_cid.is_some() {
+        record["updatedAt"] = now.into();
+    }
+    match record.as_object_mut() {
+        Some(fields) if tags.is_empty() => {
+            fields.remove("tags");
+        }
+        _ => record["tags"] = tags.into(),
+    }
+
+    let written = match &rkey {
+        Some(rkey) => {
+            let input = json!({
+                "repo": session.did,
+                "collection": ENTRY_COLLECTION,
+                "rkey": rkey,
+                "record": record,
+                "swapRecord": swap_cid,
+            });
+            state
+                .oauth
+                .call_procedure::<WriteOutput>(&mut session, "com.atproto.repo.putRecord", &input)
+                .await
+        }
+        None => {
+            let input = json!({
+                "repo": session.did,
+                "collection": ENTRY_COLLECTION,
+                "record": record,
+            });
+            state
+                .oauth
+                .call_procedure::<WriteOutput>(&mut session, "com.atproto.repo.createRecord", &input)
+                .await
+        }
+    };
+    state.sessions.put(&session_key(&session.id), &session, SESSION_TTL).await?;
+    let written = match written {
+        Ok(written) => written,
+        Err(e) if XrpcError::of(&e).is_some_and(|e| e.error == "InvalidSwap") => {
+            page.error = Some(CONFLICT.to_string());
+            return Ok(render(&page)?.into_response());
+        }
+        Err(e) => {
+            tracing::warn!("failed to write {}'s entry to their PDS: {e}", session.did);
+            page.error = Some("Couldn't save to your account. Try signing in again.".to_string());
+            return Ok(render(&page)?.into_response());
+        }
+    };
+
+    // Index the entry straight away so the author sees it saved, without
+    // waiting for it to come back through the firehose.
+    let record = serde_json::from_value(record).map_err(|e| anyhow!(e))?;
+    let entry = records::entry(&session.did, &written.uri, &written.cid, record);
+    state.db.put_entry(&entry).await?;
+
+    Ok(if entry.status.as_deref() == Some("draft") {
+        Redirect::to(&format!("/editor/{}?saved", entry.rkey)).into_response()
+    } else {
+        Redirect::to(&format!("/{}/{}", entry.did, entry.rkey)).into_response()
+    })
+}
+
+fn validate(title: &str, content: &str, tags: &[String]) -> Option<String> {
+    if title.is_empty() || title.len() > MAX_TITLE_LENGTH {
+        return Some(format!("Titles can't be empty or longer than {MAX_TITLE_LENGTH} bytes."));
+    }
+    if content.trim().is_empty() || content.len() > MAX_CONTENT_LENGTH {
+        return Some(format!("Entries can't be empty or longer than {MAX_CONTENT_LENGTH} bytes."));
+    }
+    if tags.len() > MAX_TAGS {
+        return Some(format!("Entries can have at most {MAX_TAGS} tags."));
+    }
+    if tags.iter().any(|tag| tag.graphemes(true).count() > MAX_TAG_GRAPHEMES) {
+        return Some(format!("Tags can't be longer than {MAX_TAG_GRAPHEMES} characters."));
+    }
+    if tags.iter().any(|tag| tag.len() > MAX_TAG_LENGTH) {
+        return Some(format!("Tags can't be longer than {MAX_TAG_LENGTH} bytes."));
+    }
+    None
+}
+
+#[derive(Deserialize)]
+struct WriteOutput {
+    uri: String,
+    cid: String,
+}
+
+#[derive(Deserialize)]
+struct GetRecordOutput {
+    cid: Option<String>,
+    value: Value,
+}
+
+/// Fetches one of the author's entries and its CID from their repo.
+async fn get_record(state: &AppState, session: &mut Session, rkey: &str) -> Result<Option<(Value, String)>> {
+    let params = [("repo", session.did.clone()), ("collection", ENTRY_COLLECTION.to_string()), ("rkey", rkey.to_string())];
+    let params = params.iter().map(|(name, value)| (*name, value.as_str())).collect::<Vec<_>>();
+    let output = state
+        .oauth
+        .call_query::<GetRecordOutput>(session, "com.atproto.repo.getRecord", &params)
+        .await;
+    state.sessions.put(&session_key(&session.id), session, SESSION_TTL).await?;
+
+    match output {
+        Ok(GetRecordOutput { cid: Some(cid), value }) => Ok(Some((value, cid))),
+        Ok(_) => Err(anyhow!("{rkey} was returned without a CID").into()),
+        Err(e) if XrpcError::of(&e).is_some_and(|e| e.error == "RecordNotFound") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_limited_in_graphemes_and_bytes() {
+        let tag = |tag: &str| vec![tag.to_string()];
+        assert!(validate("Title", "Content", &tag(&"é".repeat(MAX_TAG_GRAPHEMES))).is_none());
+        assert!(validate("Title", "Content", &tag(&"é".repeat(MAX_TAG_GRAPHEMES + 1))).is_some());
+
+        // Each family emoji is one grapheme of 25 bytes.
+        let family = "👨‍👩‍👧‍👦";
+        let error = validate("Title", "Content", &tag(&family.repeat(MAX_TAG_GRAPHEMES))).unwrap();
+        assert!(error.contains("bytes"), "{error}");
+    }
+
+    #[test]
+    fn titles_are_limited_in_bytes() {
+        assert!(validate(&"é".repeat(MAX_TITLE_LENGTH / 2), "Content", &[]).is_none());
+        let error = validate(&"é".repeat(MAX_TITLE_LENGTH / 2 + 1), "Content", &[]).unwrap();
+        assert!(error.contains(&format!("{MAX_TITLE_LENGTH} bytes")), "{error}");
+    }
+}
@@ -0,0 +1,9 @@
+use axum::{extract::State, Json};
+use blogi_errors::{BlogiError, Result};
+use serde_json::Value;
+
+use crate::state::AppState;
+
+pub async fn did_document(State(state): State<AppState>) -> Result<Json<Value>> {
+    state.identity.did_document().map(Json).ok_or(BlogiError::NotFound)
+}
@@ -1 +1,12 @@
+pub mod actor;
+pub mod blob;
+pub mod blog;
+pub mod editor;
 pub mod health;
+pub mod identity;
+pub mod oauth;
+pub mod oembed;
+pub mod og;
+pub mod post;
+pub mod revisions;
+pub mod tags;
@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use axum_extra::extract::{cookie::{Cookie, SameSite}, CookieJar};
+use blogi_errors::Result;
+use serde::Deserialize;
+
+use crate::{
+    oauth::{random_token, PendingAuthorization, PENDING_TTL},
+    session::{session_key, Session, SESSION_COOKIE, SESSION_TTL},
+    state::AppState,
+    templates::{render, LoginPage},
+};
+
+/// Ties a sign-in to the browser that started it, so nobody can finish
+/// signing someone else in as themselves.
+const STATE_COOKIE: &str = "blogi_oauth_state";
+
+fn pending_key(state: &str) -> String {
+    format!("oauth:{state}")
+}
+
+/// Only local paths are followed after signing in or out.
+fn safe_return_to(return_to: Option<&str>) -> &str {
+    match return_to {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") && !path.starts_with("/\\") => path,
+        _ => "/",
+    }
+}
+
+fn cookie(state: &AppState, name: &'static str, value: String, max_age: std::time::Duration) -> Cookie<'static> {
+    Cookie::build((name, value))
+        .path("/")
+        .http_only(true)
+        .secure(state.config.is_https())
+        .same_site(SameSite::Lax)
+        .max_age(cookie::time::Duration::seconds(max_age.as_secs() as i64))
+        .build()
+}
+
+fn removal(name: &'static str) -> Cookie<'static> {
+    Cookie::build(name).path("/").build()
+}
+
+pub async fn client_metadata(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.oauth.client_metadata())
+}
+
+#[derive(Deserialize)]
+pub struct LoginParams {
+    return_to: Option<String>,
+}
+
+pub async fn login_page(session: Option<Session>, Query(params): Query<LoginParams>) -> Result<Response> {
+    let return_to = safe_return_to(params.return_to.as_deref());
+    if session.is_some() {
+        return Ok(Redirect::to(return_to).into_response());
+    }
+
+    Ok(render(&LoginPage {
+        handle: String::new(),
+        return_to: return_to.to_string(),
+        error: None,
+    })?
+    .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    handle: String,
+    return_to: Option<String>,
+}
+
+pub async fn login(State(state): State<AppState>, jar: CookieJar, Form(form): Form<LoginForm>) -> Result<Response> {
+    let return_to = safe_return_to(form.return_to.as_deref());
+    let started = state.oauth.authorize(&state.did_resolver, &form.handle, return_to).await;
+    let (oauth_state, pending, authorize_url) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            tracing::info!("couldn't start signing in as {}: {e}", form.handle);
+            return Ok(render(&LoginPage {
+                handle: form.handle,
+                return_to: return_to.to_string(),
+                error: Some("Couldn't find an account with that handle, or its server doesn't support signing in."),
+            })?
+            .into_response());
+        }
+    };
+
+    state.sessions.put(&pending_key(&oauth_state), &pending, PENDING_TTL).await?;
+    let jar = jar.add(cookie(&state, STATE_COOKIE, oauth_state, PENDING_TTL));
+    Ok((jar, Redirect::to(&authorize_url)).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct CallbackParams {
+    state: Option<String>,
+    iss: Option<String>,
+    code: Option<String>,
+    error: Option<String>,
+}
+
+pub async fn callback(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Query(params): Query<CallbackParams>,
+) -> Result<Response> {
+    let failed = |jar: CookieJar, error: &'static str| -> Result<Response> {
+        Ok((
+            jar.remove(removal(STATE_COOKIE)),
+            render(&LoginPage {
+                handle: String::new(),
+                return_to: "/".to_string(),
+                error: Some(error),
+            })?,
+        )
+            .into_response())
+    };
+
+    let expected = jar.get(STATE_COOKIE).map(|cookie| cookie.value().to_string());
+    let Some(oauth_state) = params.state.filter(|oauth_state| Some(oauth_state) == expected.as_ref()) else {
+        return failed(jar, "This sign-in was started in another browser or has expired. Please try again.");
+    };
+    let Some(pending) = state.sessions.take::<PendingAuthorization>(&pending_key(&oauth_state)).await? else {
+        return failed(jar, "This sign-in has expired. Please try again.");
+    };
+    if let Some(error) = params.error {
+        tracing::info!("authorization server refused signing in {}: {error}", pending.did);
+        return failed(jar, "Signing in was cancelled or refused.");
+    }
+    let (Some(code), true) = (params.code, params.iss.as_deref() == Some(pending.issuer.as_str())) else {
+        return failed(jar, "The authorization server's response didn't match this sign-in.");
+    };
+
+    let return_to = pending.return_to.clone();
+    let session = match state.oauth.exchange(pending, &code).await {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::warn!("couldn't finish signing in: {e}");
+            return failed(jar, "Couldn't finish signing in. Please try again.");
+        }
+    };
+
+    let id = random_token();
+    state.sessions.put(&session_key(&id), &session, SESSION_TTL).await?;
+    let jar = jar
+        .remove(removal(STATE_COOKIE))
+        .add(cookie(&state, SESSION_COOKIE, id, SESSION_TTL));
+    Ok((jar, Redirect::to(safe_return_to(Some(&return_to)))).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct LogoutForm {
+    return_to: Option<String>,
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    jar: CookieJar,
+    Form(form): Form<LogoutForm>,
+) -> Result<impl IntoResponse> {
+    if let Some(session) = session {
+        state.sessions.delete(&session_key(&session.id)).await?;
+    }
+    Ok((
+        jar.remove(removal(SESSION_COOKIE)),
+        Redirect::to(safe_return_to(form.return_to.as_deref())),
+    ))
+}
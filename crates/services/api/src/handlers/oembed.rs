@@ -0,0 +1,133 @@
+use axum::{extract::{Query, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use blogi_errors::{BlogiError, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    meta::{author_name, content_format},
+    og,
+    render::excerpt,
+    state::AppState,
+    templates::PostEmbed,
+    views,
+};
+
+const DEFAULT_WIDTH: u32 = 550;
+const DEFAULT_HEIGHT: u32 = 200;
+const EXCERPT_LENGTH: usize = 280;
+
+#[derive(Deserialize)]
+pub struct OEmbedParams {
+    url: String,
+    format: Option<String>,
+    maxwidth: Option<u32>,
+    maxheight: Option<u32>,
+}
+
+/// A `rich` oEmbed response, see https://oembed.com/#section2.3.
+#[derive(Serialize)]
+pub struct OEmbedResponse {
+    version: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: String,
+    author_name: String,
+    provider_name: &'static str,
+    provider_url: String,
+    html: String,
+    width: u32,
+    height: u32,
+    thumbnail_url: String,
+    thumbnail_width: u32,
+    thumbnail_height: u32,
+}
+
+pub async fn oembed(
+    State(AppState { db, config, .. }): State<AppState>,
+    Query(params): Query<OEmbedParams>,
+) -> Result<Response> {
+    if params.format.as_deref().is_some_and(|format| format != "json") {
+        return Ok(StatusCode::NOT_IMPLEMENTED.into_response());
+    }
+
+    let (actor, rkey) = post_path(&config.public_url, &params.url).ok_or(BlogiError::NotFound)?;
+
+    let author = db.get_actor(&actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = db.get_entry(&author.did, &rkey, None).await?.ok_or(BlogiError::NotFound)?;
+    let post = views::post_view(&config, &entry, &author)?;
+
+    let url = config.post_url(&author.did, &rkey);
+    let author = author_name(&post.author);
+    let html = askama::Template::render(&PostEmbed {
+        post: &post,
+        url: &url,
+        author,
+        excerpt: excerpt(&post.record.content, content_format(&post), EXCERPT_LENGTH),
+    })
+    .map_err(anyhow::Error::from)?;
+
+    Ok(Json(OEmbedResponse {
+        version: "1.0",
+        kind: "rich",
+        title: post.record.title.clone(),
+        author_name: author.to_string(),
+        provider_name: "Blogi",
+        provider_url: config.url_for(""),
+        html,
+        width: params.maxwidth.map_or(DEFAULT_WIDTH, |max| max.min(DEFAULT_WIDTH)),
+        height: params.maxheight.map_or(DEFAULT_HEIGHT, |max| max.min(DEFAULT_HEIGHT)),
+        thumbnail_url: config.og_image_url(&entry.did, &rkey),
+        thumbnail_width: og::WIDTH,
+        thumbnail_height: og::HEIGHT,
+    })
+    .into_response())
+}
+
+/// The actor and record key of a post page on this service, i.e. a URL on
+/// the same origin as `public_url` whose path is its path plus
+/// `/<actor>/<rkey>`.
+fn post_path(public_url: &str, url: &str) -> Option<(String, String)> {
+    let (public_url, url) = (Url::parse(public_url).ok()?, Url::parse(url).ok()?);
+    if url.origin() != public_url.origin() {
+        return None;
+    }
+
+    let mut segments = url.path_segments()?;
+    for base in public_url.path_segments()?.filter(|segment| !segment.is_empty()) {
+        if segments.next()? != base {
+            return None;
+        }
+    }
+    match segments.collect::<Vec<_>>()[..] {
+        [actor, rkey] | [actor, rkey, ""] if !actor.is_empty() && !rkey.is_empty() => {
+            Some((actor.to_string(), rkey.to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::post_path;
+
+    fn path(actor: &str, rkey: &str) -> Option<(String, String)> {
+        Some((actor.to_string(), rkey.to_string()))
+    }
+
+    #[test]
+    fn finds_posts_on_this_service() {
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test/alice.test/3k"), path("alice.test", "3k"));
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test/alice.test/3k/"), path("alice.test", "3k"));
+        assert_eq!(post_path("https://blogi.test/blog", "https://blogi.test/blog/alice.test/3k"), path("alice.test", "3k"));
+    }
+
+    #[test]
+    fn ignores_other_origins_and_paths() {
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test.evil/alice.test/3k"), None);
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test@evil.test/alice.test/3k"), None);
+        assert_eq!(post_path("https://blogi.test", "http://blogi.test/alice.test/3k"), None);
+        assert_eq!(post_path("https://blogi.test/blog", "https://blogi.test/blogx/alice.test/3k"), None);
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test/alice.test/3k/revisions"), None);
+        assert_eq!(post_path("https://blogi.test", "https://blogi.test/alice.test"), None);
+    }
+}
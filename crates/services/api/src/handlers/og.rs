@@ -0,0 +1,59 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use blogi_errors::{BlogiError, Result};
+use chrono::{DateTime, Utc};
+
+use crate::{cache::{http_date, Validator}, og::render_entry_card, state::AppState};
+
+pub async fn og_image(
+    State(AppState { db, blobs, og_images, .. }): State<AppState>,
+    Path((actor, file)): Path<(String, String)>,
+) -> Result<Response> {
+    let rkey = file.strip_suffix(".png").ok_or(BlogiError::NotFound)?;
+    let author = db.get_actor(&actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = db.get_entry(&author.did, rkey, None).await?.ok_or(BlogiError::NotFound)?;
+
+    // Cards show the author's name and avatar as well as the entry, so a
+    // profile change needs a new card too.
+    let mut validator = Validator::default();
+    validator
+        .add(&entry.cid)
+        .add(author.avatar_cid.as_deref().unwrap_or_default())
+        .add(author.indexed_at.to_rfc3339());
+    let etag = validator.etag();
+
+    if let Some(png) = og_images.get(&etag).await {
+        return Ok(card_response(png, etag, entry.indexed_at.max(author.indexed_at)));
+    }
+
+    let card = render_entry_card(&blobs, &entry, &author).await?;
+    if card.missing_avatar {
+        // Don't let anyone hold on to a card without the avatar it should
+        // have, or validate it as the complete one later. The cache layer
+        // tags it with a digest of its own instead.
+        return Ok((
+            [(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "public, max-age=300")],
+            card.png,
+        )
+            .into_response());
+    }
+    og_images.insert(etag.clone(), card.png.clone()).await;
+    Ok(card_response(card.png, etag, entry.indexed_at.max(author.indexed_at)))
+}
+
+fn card_response(png: Bytes, etag: String, modified: DateTime<Utc>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "image/png".to_string()),
+            (header::CACHE_CONTROL, "public, max-age=86400".to_string()),
+            (header::ETAG, etag),
+            (header::LAST_MODIFIED, http_date(modified)),
+        ],
+        png,
+    )
+        .into_response()
+}
@@ -0,0 +1,268 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use atrium_api::types::string::{Cid, Datetime};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use blogi_db::comment::Comment;
+use blogi_errors::{BlogiError, Result};
+use blogi_lexicons::{com::atproto::repo::strong_ref, moe::hayden::blogi::blog::comment};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    auth::Viewer,
+    cache::Validator,
+    meta::{content_format, PageMeta},
+    pagination,
+    render::{content_to_html, inline_image_cids},
+    session::{session_key, Session, SESSION_TTL},
+    state::AppState,
+    templates::{render, AccountNav, CommentForm, CommentSummary, PostPage, SeriesNav},
+    views,
+};
+
+const COMMENT_COLLECTION: &str = "moe.hayden.blogi.blog.comment";
+
+/// The lexicon's limit on comment length, in bytes.
+const MAX_COMMENT_LENGTH: usize = 2048;
+
+/// Comments shown on a post page before linking to the next page of them.
+const COMMENTS_PER_PAGE: usize = 100;
+
+#[derive(Deserialize)]
+pub struct PostPageParams {
+    /// Where the page of comments starts.
+    comments: Option<String>,
+}
+
+pub async fn post_page(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Path((actor, rkey)): Path<(String, String)>,
+    Query(params): Query<PostPageParams>,
+) -> Result<impl IntoResponse> {
+    render_post_page(&state, session.as_ref(), &actor, &rkey, params.comments.as_deref(), "", None).await
+}
+
+async fn render_post_page(
+    AppState { db, config, bsky, .. }: &AppState,
+    session: Option<&Session>,
+    actor: &str,
+    rkey: &str,
+    comments_cursor: Option<&str>,
+    draft: &str,
+    error: Option<&'static str>,
+) -> Result<Response> {
+    let viewer = session.map(Session::viewer);
+    let author = db.get_actor(actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = db
+        .get_entry(&author.did, rkey, Viewer::did_of(viewer.as_ref()))
+        .await?
+        .ok_or(BlogiError::NotFound)?;
+    let post = views::post_view_detailed(db.as_ref().as_ref(), config, viewer.as_ref(), &entry, &author).await?;
+    let edited = !db.list_entry_revisions(&entry.uri, Viewer::did_of(viewer.as_ref())).await?.is_empty();
+    let comments_cursor = pagination::cursor(config, comments_cursor)?;
+    let comments = views::comment_views(
+        db.as_ref().as_ref(),
+        config,
+        bsky,
+        &entry,
+        COMMENTS_PER_PAGE,
+        comments_cursor.as_ref(),
+    )
+    .await?;
+
+    // The page is made of the entry, its author, the series and comments
+    // around it, and who's reading, so it's tagged by their versions.
+    let mut validator = Validator::default();
+    validator
+        .add(session.map_or("", |session| session.did.as_str()))
+        .add(&entry.cid)
+        .add(author.indexed_at.to_rfc3339())
+        .add([edited as u8]);
+    for membership in post.series.iter().flatten() {
+        validator
+            .add(&membership.uri)
+            .add(&membership.title)
+            .add(format!("{}/{}", membership.position, membership.total));
+        for link in [&membership.prev, &membership.next] {
+            validator
+                .add(link.as_ref().map_or("", |link| link.uri.as_str()))
+                .add(link.as_ref().map_or("", |link| link.title.as_str()));
+        }
+    }
+    for comment in &comments.comments {
+        let author = &comment.author;
+        validator
+            .add(&comment.uri)
+            .add(comment.cid.as_ref().to_string())
+            .add(author.display_name.as_deref().unwrap_or_default())
+            .add(author.handle.as_ref().map_or("", |handle| handle.as_str()));
+    }
+    validator.add(comments.cursor.as_deref().unwrap_or_default());
+
+    let format = content_format(&post);
+    let images = post.record.images.as_deref().unwrap_or_default();
+    let inline = inline_image_cids(&post.record.content, format);
+    let page = render(&PostPage {
+        account: AccountNav::new(session, &format!("/{actor}/{rkey}")),
+        meta: PageMeta::for_post(config, &post, rkey),
+        content: content_to_html(&post.record.content, format, images),
+        tags: post
+            .record
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| (tag.clone(), config.tag_url(Some(&entry.did), tag)))
+            .collect(),
+        gallery: images
+            .iter()
+            .filter(|image| !inline.contains(&image.cid.as_ref().to_string()))
+            .collect(),
+        series: post
+            .series
+            .iter()
+            .flatten()
+            .map(|membership| SeriesNav::new(config, &post, membership))
+            .collect(),
+        bsky_url: post.record.bsky_post_ref.as_ref().map(|post_ref| {
+            format!("https://bsky.app/profile/{}/post/{}", views::did(&post_ref.uri), views::rkey(&post_ref.uri))
+        }),
+        revisions_url: edited.then(|| config.url_for(&format!("{}/{rkey}/revisions", entry.did))),
+        edit_url: session.filter(|session| session.did == entry.did).map(|_| format!("/editor/{rkey}")),
+        comments: comments.comments.iter().map(CommentSummary::new).collect(),
+        comments_next_url: comments.cursor.map(|cursor| {
+            let cursor = url::form_urlencoded::byte_serialize(cursor.as_bytes()).collect::<String>();
+            format!("/{actor}/{rkey}?comments={cursor}#comments")
+        }),
+        comment_form: CommentForm {
+            action: format!("/{actor}/{rkey}/comments"),
+            content: draft.to_string(),
+            error,
+        },
+        post: &post,
+    })?;
+    Ok(([(header::ETAG, validator.etag())], page).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct CommentParams {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct CreateRecordOutput {
+    uri: String,
+    cid: String,
+}
+
+/// Writes a comment to the reader's repo, then indexes it straight away so
+/// it shows before the firehose catches up.
+pub async fn post_comment(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    Path((actor, rkey)): Path<(String, String)>,
+    Form(params): Form<CommentParams>,
+) -> Result<Response> {
+    let Some(mut session) = session else {
+        let return_to = url::form_urlencoded::byte_serialize(format!("/{actor}/{rkey}").as_bytes()).collect::<String>();
+        return Ok(Redirect::to(&format!("/login?return_to={return_to}")).into_response());
+    };
+
+    let content = params.content.trim();
+    if let Some(error) = validate_comment(content) {
+        return render_post_page(&state, Some(&session), &actor, &rkey, None, content, Some(error)).await;
+    }
+
+    let author = state.db.get_actor(&actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = state
+        .db
+        .get_entry(&author.did, &rkey, Some(&session.did))
+        .await?
+        .ok_or(BlogiError::NotFound)?;
+
+    let created_at = Utc::now();
+    let record = comment::RecordData {
+        content: content.to_string(),
+        created_at: Some(Datetime::new(created_at.fixed_offset())),
+        post: strong_ref::MainData {
+            cid: Cid::from_str(&entry.cid).map_err(|e| anyhow!(e))?,
+            uri: entry.uri.clone(),
+        }
+        .into(),
+        updated_at: None,
+    };
+    let mut record = serde_json::to_value(record).map_err(|e| anyhow!(e))?;
+    record["$type"] = COMMENT_COLLECTION.into();
+    let input = json!({
+        "repo": session.did,
+        "collection": COMMENT_COLLECTION,
+        "record": record,
+    });
+
+    let created = state
+        .oauth
+        .call_procedure::<CreateRecordOutput>(&mut session, "com.atproto.repo.createRecord", &input)
+        .await;
+    // Tokens and nonces may have changed even if the call failed.
+    state.sessions.put(&session_key(&session.id), &session, SESSION_TTL).await?;
+    let created = match created {
+        Ok(created) => created,
+        Err(e) => {
+            tracing::warn!("failed to write {}'s comment to their PDS: {e}", session.did);
+            let error = "Couldn't save your comment to your account. Try signing in again.";
+            return render_post_page(&state, Some(&session), &actor, &rkey, None, content, Some(error)).await;
+        }
+    };
+
+    let comment_rkey = views::rkey(&created.uri).to_string();
+    state
+        .db
+        .put_comment(&Comment {
+            rkey: comment_rkey.clone(),
+            uri: created.uri,
+            cid: created.cid,
+            did: session.did.clone(),
+            post_uri: entry.uri,
+            post_cid: entry.cid,
+            content: content.to_string(),
+            created_at,
+            updated_at: None,
+            indexed_at: created_at,
+        })
+        .await?;
+
+    Ok(Redirect::to(&format!("/{actor}/{rkey}#comment-{comment_rkey}")).into_response())
+}
+
+/// Checks a comment against the lexicon's limits, returning what's wrong.
+fn validate_comment(content: &str) -> Option<&'static str> {
+    if content.is_empty() || content.len() > MAX_COMMENT_LENGTH {
+        return Some("Comments can't be empty or longer than 2048 bytes.");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_are_limited_in_bytes() {
+        assert!(validate_comment(&"é".repeat(MAX_COMMENT_LENGTH / 2)).is_none());
+
+        // Few enough characters, but too many bytes.
+        let content = "é".repeat(MAX_COMMENT_LENGTH / 2 + 1);
+        assert!(content.chars().count() < MAX_COMMENT_LENGTH);
+        let error = validate_comment(&content).unwrap();
+        assert!(error.contains(&format!("{MAX_COMMENT_LENGTH} bytes")), "{error}");
+
+        assert!(validate_comment("").is_some());
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{extract::{Path, Query, State}, http::header, response::IntoResponse};
+use blogi_db::entry::{Entry, EntryRevision};
+use blogi_errors::{BlogiError, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+
+use crate::{
+    auth::Viewer,
+    cache::{http_date, Validator},
+    config::ApiConfig,
+    render::line_diff,
+    session::Session,
+    state::AppState,
+    templates::{render, DiffPage, RevisionsPage, VersionSummary},
+};
+
+/// One version of an entry, either the current one or a revision.
+struct Version {
+    cid: String,
+    title: String,
+    content: String,
+    date: DateTime<Utc>,
+}
+
+impl Version {
+    fn summary(&self, diff_url: Option<String>) -> VersionSummary {
+        VersionSummary {
+            cid: self.cid.clone(),
+            title: self.title.clone(),
+            datetime: self.date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            date: self.date.format("%B %-d, %Y %H:%M UTC").to_string(),
+            diff_url,
+        }
+    }
+}
+
+impl From<&Entry> for Version {
+    fn from(entry: &Entry) -> Version {
+        Version {
+            cid: entry.cid.clone(),
+            title: entry.title.clone(),
+            content: entry.content.clone(),
+            date: entry.updated_at.unwrap_or(entry.indexed_at),
+        }
+    }
+}
+
+impl From<EntryRevision> for Version {
+    fn from(revision: EntryRevision) -> Version {
+        Version {
+            cid: revision.cid,
+            title: revision.title,
+            content: revision.content,
+            date: revision.updated_at.unwrap_or(revision.indexed_at),
+        }
+    }
+}
+
+fn diff_url(config: &ApiConfig, entry: &Entry, from: &str, to: &str) -> String {
+    let base = config.url_for(&format!("{}/{}/diff", entry.did, entry.rkey));
+    if to == entry.cid {
+        format!("{base}?from={from}")
+    } else {
+        format!("{base}?from={from}&to={to}")
+    }
+}
+
+pub async fn revisions_page(
+    State(AppState { db, config, .. }): State<AppState>,
+    session: Option<Session>,
+    Path((actor, rkey)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+    let viewer = session.as_ref().map(Session::viewer);
+    let viewer = Viewer::did_of(viewer.as_ref());
+    let author = db.get_actor(&actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = db.get_entry(&author.did, &rkey, viewer).await?.ok_or(BlogiError::NotFound)?;
+
+    // Newest first, so each version is diffed against the one that replaced it.
+    let mut versions = vec![Version::from(&entry)];
+    versions.extend(db.list_entry_revisions(&entry.uri, viewer).await?.into_iter().map(Version::from));
+    let summaries = versions
+        .iter()
+        .enumerate()
+        .map(|(idx, version)| {
+            let newer = idx.checked_sub(1).map(|newer| &versions[newer]);
+            version.summary(newer.map(|newer| diff_url(&config, &entry, &version.cid, &newer.cid)))
+        })
+        .collect();
+
+    // Versions never change once written, so the page only changes when one
+    // is added or becomes visible.
+    let mut validator = Validator::default();
+    for version in &versions {
+        validator.add(&version.cid);
+    }
+
+    let page = render(&RevisionsPage {
+        title: entry.title.clone(),
+        post_url: config.post_url(&entry.did, &entry.rkey),
+        versions: summaries,
+    })?;
+    Ok((
+        [(header::ETAG, validator.etag()), (header::LAST_MODIFIED, http_date(entry.indexed_at))],
+        page,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct DiffParams {
+    from: String,
+    /// Defaults to the current version.
+    to: Option<String>,
+}
+
+pub async fn diff_page(
+    State(AppState { db, config, .. }): State<AppState>,
+    session: Option<Session>,
+    Path((actor, rkey)): Path<(String, String)>,
+    Query(params): Query<DiffParams>,
+) -> Result<impl IntoResponse> {
+    let viewer = session.as_ref().map(Session::viewer);
+    let viewer = Viewer::did_of(viewer.as_ref());
+    let author = db.get_actor(&actor).await?.ok_or(BlogiError::NotFound)?;
+    let entry = db.get_entry(&author.did, &rkey, viewer).await?.ok_or(BlogiError::NotFound)?;
+
+    let mut versions = vec![Version::from(&entry)];
+    versions.extend(db.list_entry_revisions(&entry.uri, viewer).await?.into_iter().map(Version::from));
+    let find = |cid: &str| versions.iter().find(|version| version.cid == cid).ok_or(BlogiError::NotFound);
+    let from = find(&params.from)?;
+    let to = find(params.to.as_deref().unwrap_or(&entry.cid))?;
+
+    // The title shown is the current one; everything else comes from the
+    // two versions being compared.
+    let mut validator = Validator::default();
+    validator.add(&entry.cid).add(&from.cid).add(&to.cid);
+
+    let page = render(&DiffPage {
+        title: entry.title.clone(),
+        post_url: config.post_url(&entry.did, &entry.rkey),
+        revisions_url: config.url_for(&format!("{}/{}/revisions", entry.did, entry.rkey)),
+        title_changed: from.title != to.title,
+        hunks: line_diff(&from.content, &to.content),
+        from: from.summary(None),
+        to: to.summary(None),
+    })?;
+    Ok(([(header::ETAG, validator.etag())], page))
+}
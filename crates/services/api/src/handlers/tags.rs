@@ -0,0 +1,155 @@
+use axum::{extract::{Path, Query, State}, http::{header, Uri}, response::IntoResponse};
+use blogi_db::entry::normalize_tag;
+use blogi_errors::{BlogiError, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+
+use crate::{
+    auth::Viewer,
+    cache::Validator,
+    config::ApiConfig,
+    meta::author_name,
+    pagination,
+    session::Session,
+    state::AppState,
+    templates::{render, AccountNav, AtomFeed, PostSummary, TagPage},
+    views,
+};
+
+/// Tag pages exist both network-wide and for a single blog.
+#[derive(Deserialize)]
+pub struct TagPath {
+    actor: Option<String>,
+    tag: String,
+}
+
+#[derive(Deserialize)]
+pub struct TagPageParams {
+    cursor: Option<String>,
+}
+
+struct Listing {
+    tag: String,
+    /// The DID and display name of the blog being listed, if any.
+    author: Option<(String, String)>,
+    posts: Vec<PostSummary>,
+    cursor: Option<String>,
+    /// Tags the listing by the records on it and who's reading it.
+    etag: String,
+}
+
+impl Listing {
+    fn page_url(&self, config: &ApiConfig) -> String {
+        config.tag_url(self.author.as_ref().map(|(did, _)| did.as_str()), &self.tag)
+    }
+
+    fn feed_url(&self, config: &ApiConfig) -> String {
+        format!("{}/atom.xml", self.page_url(config))
+    }
+}
+
+async fn load(state: &AppState, path: TagPath, viewer: Option<&Viewer>, cursor: Option<String>) -> Result<Listing> {
+    let author = match &path.actor {
+        Some(actor) => Some(state.db.get_actor(actor).await?.ok_or(BlogiError::NotFound)?),
+        None => None,
+    };
+
+    let limit = pagination::limit(None);
+    let cursor = pagination::cursor(&state.config, cursor.as_deref())?;
+    let entries = state
+        .db
+        .list_entries_by_tag(
+            &path.tag,
+            author.as_ref().map(|a| a.did.as_str()),
+            Viewer::did_of(viewer),
+            limit as i64,
+            cursor.as_ref(),
+        )
+        .await?;
+
+    let posts = views::post_views(state.db.as_ref().as_ref(), &state.config, viewer, &entries).await?;
+    let author = match author {
+        Some(author) => {
+            let profile = views::profile_view(&state.config, &author)?;
+            Some((author.did, author_name(&profile).to_string()))
+        }
+        None => None,
+    };
+    let cursor = pagination::next_cursor(&state.config, &entries, limit);
+
+    let mut validator = Validator::default();
+    validator
+        .add(Viewer::did_of(viewer).unwrap_or_default())
+        .add(author.as_ref().map_or("", |(_, name)| name.as_str()))
+        .add(cursor.as_deref().unwrap_or_default());
+    for post in &posts {
+        validator.add(&post.uri).add(post.cid.as_ref().to_string()).add(author_name(&post.author));
+    }
+
+    Ok(Listing {
+        tag: normalize_tag(&path.tag),
+        author,
+        posts: posts.iter().map(|post| PostSummary::new(&state.config, post)).collect(),
+        cursor,
+        etag: validator.etag(),
+    })
+}
+
+pub async fn tag_page(
+    State(state): State<AppState>,
+    session: Option<Session>,
+    uri: Uri,
+    Path(path): Path<TagPath>,
+    Query(params): Query<TagPageParams>,
+) -> Result<impl IntoResponse> {
+    let viewer = session.as_ref().map(Session::viewer);
+    let listing = load(&state, path, viewer.as_ref(), params.cursor).await?;
+    let config = &state.config;
+
+    let page = render(&TagPage {
+        account: AccountNav::new(session.as_ref(), uri.path()),
+        feed_url: listing.feed_url(config),
+        next_url: listing.cursor.as_ref().map(|cursor| {
+            let cursor = url::form_urlencoded::byte_serialize(cursor.as_bytes()).collect::<String>();
+            format!("{}?cursor={cursor}", listing.page_url(config))
+        }),
+        author: listing.author.map(|(_, name)| name),
+        tag: listing.tag,
+        posts: listing.posts,
+    })?;
+    Ok(([(header::ETAG, listing.etag)], page))
+}
+
+pub async fn tag_feed(
+    State(state): State<AppState>,
+    Path(path): Path<TagPath>,
+) -> Result<impl IntoResponse> {
+    // Feeds are fetched without cookies, so only ever list what's public.
+    let listing = load(&state, path, None, None).await?;
+    let config = &state.config;
+
+    let title = match &listing.author {
+        Some((_, name)) => format!("#{} · {name}", listing.tag),
+        None => format!("#{}", listing.tag),
+    };
+
+    let etag = listing.etag.clone();
+    let feed = AtomFeed {
+        title,
+        page_url: listing.page_url(config),
+        feed_url: listing.feed_url(config),
+        updated: listing
+            .posts
+            .iter()
+            .map(|post| post.updated.clone())
+            .max()
+            .unwrap_or_else(|| Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        posts: listing.posts,
+    };
+
+    let xml = askama::Template::render(&feed).map_err(anyhow::Error::from)?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8".to_string()), (header::ETAG, etag)],
+        xml,
+    ))
+}
@@ -0,0 +1,83 @@
+use anyhow::anyhow;
+use blogi_errors::Result;
+use blogi_identity::SECP256K1_PREFIX;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::config::ApiConfig;
+
+/// The fragment clients name in `atproto-proxy` headers to reach us, as in
+/// `did:web:blogi.example#blogi_appview`.
+pub const APPVIEW_SERVICE_ID: &str = "blogi_appview";
+const APPVIEW_SERVICE_TYPE: &str = "BlogiAppView";
+
+/// This service's own atproto identity.
+pub struct ServiceIdentity {
+    did: String,
+    public_url: String,
+    signing_key: k256::ecdsa::SigningKey,
+}
+
+impl ServiceIdentity {
+    pub fn new(config: &ApiConfig) -> Result<ServiceIdentity> {
+        let key = match &config.signing_key {
+            Some(hex) => decode_hex(hex.trim())?,
+            // Without a key of its own the service derives one, so changing
+            // SECRET_KEY changes its identity too.
+            None => Sha256::new()
+                .chain_update(b"blogi service signing key\0")
+                .chain_update(config.secret_key.as_bytes())
+                .finalize()
+                .to_vec(),
+        };
+
+        Ok(ServiceIdentity {
+            did: config.service_did.clone(),
+            public_url: config.public_url.clone(),
+            signing_key: k256::ecdsa::SigningKey::from_slice(&key).map_err(|e| anyhow!(e))?,
+        })
+    }
+
+    /// The DID document published at `/.well-known/did.json`, if this
+    /// service is a `did:web`. Other DID methods publish theirs elsewhere.
+    pub fn did_document(&self) -> Option<Value> {
+        if !self.did.starts_with("did:web:") {
+            return None;
+        }
+
+        let public_key = self.signing_key.verifying_key().to_encoded_point(true);
+        let public_key_multibase = multibase::encode(
+            multibase::Base::Base58Btc,
+            [SECP256K1_PREFIX.as_slice(), public_key.as_bytes()].concat(),
+        );
+
+        Some(json!({
+            "@context": [
+                "https://www.w3.org/ns/did/v1",
+                "https://w3id.org/security/multikey/v1",
+            ],
+            "id": self.did,
+            "verificationMethod": [{
+                "id": format!("{}#atproto", self.did),
+                "type": "Multikey",
+                "controller": self.did,
+                "publicKeyMultibase": public_key_multibase,
+            }],
+            "service": [{
+                "id": format!("#{APPVIEW_SERVICE_ID}"),
+                "type": APPVIEW_SERVICE_TYPE,
+                "serviceEndpoint": self.public_url,
+            }],
+        }))
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(anyhow!("signing key isn't hex-encoded").into());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("signing key isn't hex-encoded").into()))
+        .collect()
+}
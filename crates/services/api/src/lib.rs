@@ -1,8 +1,14 @@
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::Result;
-use axum::{body::HttpBody, extract::MatchedPath, response::Response, routing::get, Router};
+use axum::{body::HttpBody, extract::MatchedPath, middleware, response::Response, routing::{get, post}, Router};
 use http::Request;
+use blobs::BlobStore;
+use blogi_identity::DidResolver;
+use bsky::BskyClient;
+use identity::ServiceIdentity;
+use oauth::OAuthClient;
+use session::SessionStore;
 use state::AppState;
 use tokio::net::TcpListener;
 use tower_http::{timeout::TimeoutLayer, trace::TraceLayer};
@@ -10,19 +16,84 @@ use tracing::Span;
 
 mod state;
 mod handlers;
+mod auth;
+mod blobs;
+mod bsky;
+mod cache;
+mod config;
+mod identity;
+mod meta;
+mod oauth;
+mod og;
+mod pagination;
+mod render;
+mod scheduler;
+mod session;
+mod templates;
+mod views;
+mod xrpc;
 
+pub use config::ApiConfig;
+
+// `TimeoutLayer::new` is deprecated in the tower-http that reqwest pulls in,
+// but its 408 response is still the one we want.
+#[allow(deprecated)]
 pub async fn start(
     bind_addr: SocketAddr,
+    config: ApiConfig,
     _datastore: Box<dyn blogi_db::Datastore>,
 ) -> Result<()> {
+    let http = reqwest::Client::new();
+    let blobs = BlobStore::open(
+        http.clone(),
+        &config.blob_cache_dir,
+        config.blob_cache_max_size,
+    ).await?;
+    let bsky = BskyClient::new(http.clone(), &config.bsky_appview_url);
+    let did_resolver = DidResolver::new(http.clone(), &config.plc_directory_url, &config.bsky_appview_url);
+    let identity = ServiceIdentity::new(&config)?;
+    let oauth = OAuthClient::new(http, &config);
+    let sessions = SessionStore::open(&config.redis_url, &config.secret_key).await?;
+
     let state = AppState {
         db: Arc::new(_datastore),
+        config: Arc::new(config),
+        blobs: Arc::new(blobs),
+        bsky: Arc::new(bsky),
+        did_resolver: Arc::new(did_resolver),
+        identity: Arc::new(identity),
+        oauth: Arc::new(oauth),
+        sessions: Arc::new(sessions),
+        og_images: og::cache(),
     };
 
+    tokio::spawn(scheduler::run(state.db.clone()));
+
     let router = Router::new()
+        .route("/.well-known/did.json", get(handlers::identity::did_document))
         .route("/xrpc/_health", get(handlers::health::xrpc_health))
+        .merge(blogi_lexicons::server::router())
+        .route("/oembed", get(handlers::oembed::oembed))
+        .route("/editor", get(handlers::editor::entries_page))
+        .route("/editor/new", get(handlers::editor::new_entry_page).post(handlers::editor::save_new_entry))
+        .route("/editor/{rkey}", get(handlers::editor::edit_entry_page).post(handlers::editor::save_entry))
+        .route("/login", get(handlers::oauth::login_page).post(handlers::oauth::login))
+        .route("/logout", post(handlers::oauth::logout))
+        .route("/oauth/callback", get(handlers::oauth::callback))
+        .route("/oauth/client-metadata.json", get(handlers::oauth::client_metadata))
+        .route("/blob/{preset}/{did}/{cid}", get(handlers::blob::get_blob))
+        .route("/og/{actor}/{file}", get(handlers::og::og_image))
+        .route("/tags/{tag}", get(handlers::tags::tag_page))
+        .route("/tags/{tag}/atom.xml", get(handlers::tags::tag_feed))
+        .route("/{actor}/{rkey}", get(handlers::post::post_page))
+        .route("/{actor}/{rkey}/comments", post(handlers::post::post_comment))
+        .route("/{actor}/{rkey}/diff", get(handlers::revisions::diff_page))
+        .route("/{actor}/{rkey}/revisions", get(handlers::revisions::revisions_page))
+        .route("/{actor}/tags/{tag}", get(handlers::tags::tag_page))
+        .route("/{actor}/tags/{tag}/atom.xml", get(handlers::tags::tag_feed))
         .with_state(state)
 
+        .layer(middleware::from_fn(cache::layer))
         .layer(TimeoutLayer::new(Duration::from_secs(10)))
         .layer(
             TraceLayer::new_for_http()
@@ -0,0 +1,64 @@
+use atrium_api::types::string::Datetime;
+use blogi_lexicons::moe::hayden::blogi::{actor::defs::ProfileView, blog::defs::PostView};
+
+use crate::{config::ApiConfig, og, render::{excerpt, ContentFormat}};
+
+/// How many characters of post content to use as a page description.
+const DESCRIPTION_LENGTH: usize = 200;
+
+/// The name to attribute content to, preferring the display name.
+pub fn author_name(author: &ProfileView) -> &str {
+    author
+        .display_name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(author.handle.as_str())
+}
+
+pub fn content_format(post: &PostView) -> ContentFormat {
+    ContentFormat::from_mime(Some(&post.record.content_format))
+}
+
+/// When a post went live, which for scheduled posts is later than when it
+/// was written.
+pub fn published(post: &PostView) -> &Datetime {
+    post.record.publish_at.as_ref().unwrap_or(&post.record.created_at)
+}
+
+/// OpenGraph and Twitter card metadata for a rendered page.
+pub struct PageMeta {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub image: String,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub author: String,
+    pub published_time: String,
+    pub modified_time: Option<String>,
+    pub oembed_url: String,
+}
+
+impl PageMeta {
+    pub fn for_post(config: &ApiConfig, post: &PostView, rkey: &str) -> PageMeta {
+        let did = post.author.did.as_str();
+        let url = config.post_url(did, rkey);
+
+        PageMeta {
+            title: post.record.title.clone(),
+            description: excerpt(&post.record.content, content_format(post), DESCRIPTION_LENGTH),
+            image: config.og_image_url(did, rkey),
+            image_width: og::WIDTH,
+            image_height: og::HEIGHT,
+            author: author_name(&post.author).to_string(),
+            published_time: published(post).as_str().to_string(),
+            modified_time: post.record.updated_at.as_ref().map(|t| t.as_str().to_string()),
+            oembed_url: format!(
+                "{}?format=json&url={}",
+                config.url_for("oembed"),
+                url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>(),
+            ),
+            url,
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use blogi_errors::{BlogiError, Result, Success};
+use blogi_identity::DidResolver;
+use chrono::{DateTime, Utc};
+use p256::ecdsa::signature::Signer;
+use reqwest::{
+    header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+    Method, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{config::ApiConfig, session::Session};
+
+/// What readers are asked to grant. `transition:generic` lets comments be
+/// written to their repos until granular scopes land.
+pub const SCOPE: &str = "atproto transition:generic";
+
+/// How long a reader has to finish signing in at their authorization server.
+pub const PENDING_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How long a refresh's tokens are handed to requests still holding the
+/// refresh token it spent, which covers the time before the session that
+/// refreshed is saved.
+const REFRESH_REUSE: Duration = Duration::from_secs(60);
+
+/// A sign-in waiting for the authorization server to redirect back.
+#[derive(Serialize, Deserialize)]
+pub struct PendingAuthorization {
+    /// The account the reader said they were.
+    pub did: String,
+    pub handle: String,
+    pub pds_url: String,
+    pub issuer: String,
+    pub token_endpoint: String,
+    pub code_verifier: String,
+    pub dpop_key: String,
+    pub dpop_nonce: Option<String>,
+    /// The local path to send the reader back to.
+    pub return_to: String,
+}
+
+#[derive(Deserialize)]
+struct ProtectedResourceMetadata {
+    authorization_servers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AuthorizationServerMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    pushed_authorization_request_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct ParResponse {
+    request_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: Option<i64>,
+    pub refresh_token: Option<String>,
+    pub scope: String,
+    pub sub: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// An error response from an XRPC method called on a reader's PDS.
+#[derive(Debug)]
+pub struct XrpcError {
+    pub method: String,
+    pub status: StatusCode,
+    pub error: String,
+    pub message: Option<String>,
+}
+
+impl XrpcError {
+    /// The XRPC error behind `error`, if that's what it is.
+    pub fn of(error: &BlogiError) -> Option<&XrpcError> {
+        match error {
+            BlogiError::UpstreamFailure(error) => error.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for XrpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} responded with {} ({})", self.method, self.error, self.status)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for XrpcError {}
+
+/// Signs readers in through their PDS's authorization server as an atproto
+/// OAuth public client.
+pub struct OAuthClient {
+    http: reqwest::Client,
+    client_id: String,
+    client_uri: String,
+    redirect_uri: String,
+    /// The last refresh of each session, locked while one is under way.
+    /// Refresh tokens are single-use, so concurrent requests for a session
+    /// must not each spend the one they loaded.
+    refreshes: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Option<Refresh>>>>>,
+}
+
+/// Tokens a session was refreshed to, and the refresh token spent on them.
+struct Refresh {
+    spent: String,
+    at: Instant,
+    scope: String,
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+    dpop_nonce: Option<String>,
+}
+
+impl OAuthClient {
+    pub fn new(http: reqwest::Client, config: &ApiConfig) -> OAuthClient {
+        let redirect_uri = config.url_for("oauth/callback");
+
+        // Authorization servers can't fetch metadata from a loopback host,
+        // so local development uses the special `http://localhost` client,
+        // which must redirect to an IP address rather than `localhost`.
+        let loopback = url::Url::parse(&config.public_url).is_ok_and(|url| url.host_str().is_some_and(is_loopback));
+        let (client_id, redirect_uri) = if loopback {
+            let redirect_uri = redirect_uri.replacen("://localhost", "://127.0.0.1", 1);
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("redirect_uri", &redirect_uri)
+                .append_pair("scope", SCOPE)
+                .finish();
+            (format!("http://localhost?{query}"), redirect_uri)
+        } else {
+            (config.url_for("oauth/client-metadata.json"), redirect_uri)
+        };
+
+        OAuthClient {
+            http,
+            client_id,
+            client_uri: config.public_url.clone(),
+            redirect_uri,
+            refreshes: Mutex::default(),
+        }
+    }
+
+    /// The client metadata document authorization servers fetch from our
+    /// `client_id`.
+    pub fn client_metadata(&self) -> serde_json::Value {
+        json!({
+            "client_id": self.client_id,
+            "client_name": "Blogi",
+            "client_uri": self.client_uri,
+            "application_type": "web",
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "redirect_uris": [self.redirect_uri],
+            "scope": SCOPE,
+            "token_endpoint_auth_method": "none",
+            "dpop_bound_access_tokens": true,
+        })
+    }
+
+    /// Starts signing in as `identifier`, a handle or DID. Returns the state
+    /// to key the pending sign-in by, and where to send the reader.
+    pub async fn authorize(
+        &self,
+        did_resolver: &DidResolver,
+        identifier: &str,
+        return_to: &str,
+    ) -> Result<(String, PendingAuthorization, String)> {
+        let identifier = identifier.trim().trim_start_matches('@');
+        let did = if identifier.starts_with("did:") {
+            identifier.to_string()
+        } else {
+            did_resolver.resolve_handle(identifier).await?
+        };
+        let document = did_resolver.resolve(&did).await?;
+        let handle = if identifier.starts_with("did:") {
+            // The handle is only shown, but still shouldn't be one the
+            // account merely claims.
+            match document.handle() {
+                Some(handle) if did_resolver.resolve_handle(handle).await.is_ok_and(|resolved| resolved == did) => {
+                    handle.to_string()
+                }
+                _ => did.clone(),
+            }
+        } else if document.claims_handle(identifier) {
+            identifier.to_ascii_lowercase()
+        } else {
+            return Err(anyhow!("{did} doesn't claim the handle {identifier}").into());
+        };
+        let pds_url = document.pds_endpoint()?.to_string();
+        let server = self.authorization_server(&pds_url).await?;
+
+        let state = random_token();
+        let code_verifier = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+        let dpop_key = DpopKey::generate();
+        let mut dpop_nonce = None;
+
+        let par: ParResponse = self
+            .dpop_post(&server.pushed_authorization_request_endpoint, &dpop_key, &mut dpop_nonce, &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", &self.redirect_uri),
+                ("response_type", "code"),
+                ("scope", SCOPE),
+                ("state", &state),
+                ("code_challenge", &code_challenge),
+                ("code_challenge_method", "S256"),
+                ("login_hint", identifier),
+            ])
+            .await?;
+
+        let mut authorize_url = url::Url::parse(&server.authorization_endpoint).map_err(|e| anyhow!(e))?;
+        authorize_url
+            .query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("request_uri", &par.request_uri);
+
+        let pending = PendingAuthorization {
+            did,
+            handle,
+            pds_url,
+            issuer: server.issuer,
+            token_endpoint: server.token_endpoint,
+            code_verifier,
+            dpop_key: dpop_key.encode(),
+            dpop_nonce,
+            return_to: return_to.to_string(),
+        };
+        Ok((state, pending, authorize_url.into()))
+    }
+
+    /// Trades the code the authorization server redirected back with for
+    /// tokens, and checks they're for the account that started signing in.
+    pub async fn exchange(&self, pending: PendingAuthorization, code: &str) -> Result<Session> {
+        let dpop_key = DpopKey::decode(&pending.dpop_key)?;
+        let mut dpop_nonce = pending.dpop_nonce;
+        let tokens: TokenResponse = self
+            .dpop_post(&pending.token_endpoint, &dpop_key, &mut dpop_nonce, &[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.redirect_uri),
+                ("code_verifier", &pending.code_verifier),
+                ("client_id", &self.client_id),
+            ])
+            .await?;
+
+        if !tokens.token_type.eq_ignore_ascii_case("DPoP") {
+            return Err(anyhow!("authorization server issued {} tokens, not DPoP", tokens.token_type).into());
+        }
+        if tokens.sub != pending.did {
+            return Err(anyhow!("signed in as {} rather than {}", tokens.sub, pending.did).into());
+        }
+        if !tokens.scope.split(' ').any(|scope| scope == "atproto") {
+            return Err(anyhow!("authorization server didn't grant the atproto scope").into());
+        }
+
+        Ok(Session {
+            id: String::new(),
+            did: pending.did,
+            handle: pending.handle,
+            pds_url: pending.pds_url,
+            issuer: pending.issuer,
+            token_endpoint: pending.token_endpoint,
+            scope: tokens.scope,
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_at: Utc::now() + chrono::Duration::seconds(tokens.expires_in.unwrap_or(0)),
+            dpop_key: pending.dpop_key,
+            dpop_nonce,
+            pds_dpop_nonce: None,
+        })
+    }
+
+    /// Trades a session's refresh token for new tokens, one request per
+    /// session at a time.
+    pub async fn refresh(&self, session: &mut Session) -> Success {
+        let lock = {
+            let mut refreshes = self.refreshes.lock().unwrap_or_else(|e| e.into_inner());
+            refreshes.retain(|_, lock| {
+                Arc::strong_count(lock) > 1
+                    || lock.try_lock().is_ok_and(|last| last.as_ref().is_some_and(|last| last.at.elapsed() < REFRESH_REUSE))
+            });
+            refreshes.entry(session.id.clone()).or_default().clone()
+        };
+        let mut last = lock.lock().await;
+
+        // Another request may have spent our refresh token while we waited.
+        if let Some(last) = last.as_ref().filter(|last| session.refresh_token.as_ref() == Some(&last.spent)) {
+            session.scope = last.scope.clone();
+            session.access_token = last.access_token.clone();
+            session.refresh_token = last.refresh_token.clone();
+            session.expires_at = last.expires_at;
+            session.dpop_nonce = last.dpop_nonce.clone();
+            return Ok(());
+        }
+
+        let spent = session.refresh_token.clone();
+        self.refresh_tokens(session).await?;
+        // Servers that keep refresh tokens leave nothing for others to reuse.
+        *last = spent.filter(|spent| session.refresh_token.as_ref() != Some(spent)).map(|spent| Refresh {
+            spent,
+            at: Instant::now(),
+            scope: session.scope.clone(),
+            access_token: session.access_token.clone(),
+            refresh_token: session.refresh_token.clone(),
+            expires_at: session.expires_at,
+            dpop_nonce: session.dpop_nonce.clone(),
+        });
+        Ok(())
+    }
+
+    async fn refresh_tokens(&self, session: &mut Session) -> Success {
+        let dpop_key = DpopKey::decode(&session.dpop_key)?;
+        let refresh_token = session
+            .refresh_token
+            .as_deref()
+            .ok_or_else(|| anyhow!("{}'s session can't be refreshed", session.did))?;
+        let tokens: TokenResponse = self
+            .dpop_post(&session.token_endpoint, &dpop_key, &mut session.dpop_nonce, &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", &self.client_id),
+            ])
+            .await?;
+        if tokens.sub != session.did {
+            return Err(anyhow!("refreshed {}'s session as {}", session.did, tokens.sub).into());
+        }
+
+        session.scope = tokens.scope;
+        session.access_token = tokens.access_token;
+        // Refresh tokens are usually single-use, but servers may keep them.
+        session.refresh_token = tokens.refresh_token.or(session.refresh_token.take());
+        session.expires_at = Utc::now() + chrono::Duration::seconds(tokens.expires_in.unwrap_or(0));
+        Ok(())
+    }
+
+    /// Calls an XRPC query on the reader's PDS as them. See
+    /// [`OAuthClient::call_procedure`].
+    pub async fn call_query<T: DeserializeOwned>(
+        &self,
+        session: &mut Session,
+        method: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T> {
+        self.call(session, Method::GET, method, params, None).await
+    }
+
+    /// Calls an XRPC procedure on the reader's PDS as them, refreshing their
+    /// tokens when they've expired. Callers should save the session
+    /// afterwards, since its tokens and nonces may have changed. Errors the
+    /// method responds with are [`XrpcError`]s.
+    pub async fn call_procedure<T: DeserializeOwned>(
+        &self,
+        session: &mut Session,
+        method: &str,
+        input: &serde_json::Value,
+    ) -> Result<T> {
+        self.call(session, Method::POST, method, &[], Some(input)).await
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        session: &mut Session,
+        http_method: Method,
+        method: &str,
+        params: &[(&str, &str)],
+        input: Option<&serde_json::Value>,
+    ) -> Result<T> {
+        if session.expires_at <= Utc::now() + chrono::Duration::seconds(30) {
+            self.refresh(session).await?;
+        }
+
+        let url = format!("{}/xrpc/{method}", session.pds_url);
+        let dpop_key = DpopKey::decode(&session.dpop_key)?;
+        let (mut renonced, mut refreshed) = (false, false);
+        loop {
+            let proof = dpop_key.proof(
+                http_method.as_str(),
+                &url,
+                session.pds_dpop_nonce.as_deref(),
+                Some(&session.access_token),
+            )?;
+            let mut request = self
+                .http
+                .request(http_method.clone(), &url)
+                .query(params)
+                .header(AUTHORIZATION, format!("DPoP {}", session.access_token))
+                .header("DPoP", proof)
+                .timeout(Duration::from_secs(10));
+            if let Some(input) = input {
+                request = request.json(input);
+            }
+            let response = request.send().await.map_err(|e| BlogiError::UpstreamFailure(e.into()))?;
+            if let Some(fresh) = response.headers().get("DPoP-Nonce").and_then(|value| value.to_str().ok()) {
+                session.pds_dpop_nonce = Some(fresh.to_string());
+            }
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response.json().await.map_err(|e| anyhow!(e))?);
+            }
+            let challenge = response
+                .headers()
+                .get(WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            if challenge.contains("use_dpop_nonce") && !renonced {
+                renonced = true;
+                continue;
+            }
+            // The access token may have been revoked or expired early.
+            if status == StatusCode::UNAUTHORIZED && !refreshed {
+                refreshed = true;
+                self.refresh(session).await?;
+                continue;
+            }
+
+            #[derive(Deserialize)]
+            struct Body {
+                error: String,
+                message: Option<String>,
+            }
+            let body: Option<Body> = response.json().await.ok();
+            return Err(BlogiError::UpstreamFailure(anyhow!(XrpcError {
+                method: method.to_string(),
+                status,
+                error: body.as_ref().map_or_else(|| status.to_string(), |body| body.error.clone()),
+                message: body.and_then(|body| body.message),
+            })));
+        }
+    }
+
+    /// Finds the authorization server a PDS delegates to, checking its
+    /// metadata is really its own.
+    async fn authorization_server(&self, pds_url: &str) -> Result<AuthorizationServerMetadata> {
+        let resource: ProtectedResourceMetadata =
+            self.get_json(&format!("{pds_url}/.well-known/oauth-protected-resource")).await?;
+        let issuer = resource
+            .authorization_servers
+            .first()
+            .ok_or_else(|| anyhow!("{pds_url} doesn't name an authorization server"))?
+            .trim_end_matches('/');
+
+        let server: AuthorizationServerMetadata =
+            self.get_json(&format!("{issuer}/.well-known/oauth-authorization-server")).await?;
+        if server.issuer.trim_end_matches('/') != issuer {
+            return Err(anyhow!("{issuer}'s metadata is for {}", server.issuer).into());
+        }
+        Ok(server)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| BlogiError::UpstreamFailure(e.into()))?;
+        if !response.status().is_success() {
+            return Err(BlogiError::UpstreamFailure(anyhow!("fetching {url} failed with {}", response.status())));
+        }
+        Ok(response.json().await.map_err(|e| anyhow!(e))?)
+    }
+
+    /// Posts a form to an authorization server with a DPoP proof, retrying
+    /// once if it asks for a fresh nonce. `nonce` is kept up to date with
+    /// the last one the server handed out.
+    pub async fn dpop_post<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        key: &DpopKey,
+        nonce: &mut Option<String>,
+        form: &[(&str, &str)],
+    ) -> Result<T> {
+        let mut retried = false;
+        loop {
+            let proof = key.proof("POST", url, nonce.as_deref(), None)?;
+            let response = self
+                .http
+                .post(url)
+                .header("DPoP", proof)
+                .form(form)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await
+                .map_err(|e| anyhow!(e))?;
+            if let Some(fresh) = response.headers().get("DPoP-Nonce").and_then(|value| value.to_str().ok()) {
+                *nonce = Some(fresh.to_string());
+            }
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response.json().await.map_err(|e| anyhow!(e))?);
+            }
+            let error: ErrorResponse = response.json().await.map_err(|_| anyhow!("{url} responded with {status}"))?;
+            if status == StatusCode::BAD_REQUEST && error.error == "use_dpop_nonce" && !retried {
+                retried = true;
+                continue;
+            }
+            return Err(anyhow!(
+                "{url} responded with {}: {}",
+                error.error,
+                error.error_description.unwrap_or_default()
+            )
+            .into());
+        }
+    }
+}
+
+fn is_loopback(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// A random, URL-safe string for states, verifiers and session IDs.
+pub fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+/// The key a session's tokens are bound to through DPoP.
+pub struct DpopKey(p256::ecdsa::SigningKey);
+
+impl DpopKey {
+    pub fn generate() -> DpopKey {
+        DpopKey(p256::ecdsa::SigningKey::random(&mut rand::rngs::OsRng))
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.0.to_bytes())
+    }
+
+    pub fn decode(encoded: &str) -> Result<DpopKey> {
+        let bytes = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| anyhow!(e))?;
+        let key = p256::ecdsa::SigningKey::from_slice(&bytes).map_err(|e| anyhow!(e))?;
+        Ok(DpopKey(key))
+    }
+
+    /// Builds a proof for a request, bound to `access_token` when one is
+    /// being presented.
+    pub fn proof(&self, method: &str, url: &str, nonce: Option<&str>, access_token: Option<&str>) -> Result<HeaderValue> {
+        let point = self.0.verifying_key().to_encoded_point(false);
+        let (Some(x), Some(y)) = (point.x(), point.y()) else {
+            return Err(anyhow!("DPoP key has no coordinates").into());
+        };
+        let header = json!({
+            "typ": "dpop+jwt",
+            "alg": "ES256",
+            "jwk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": URL_SAFE_NO_PAD.encode(x),
+                "y": URL_SAFE_NO_PAD.encode(y),
+            },
+        });
+
+        // The URL is bound without its query or fragment.
+        let htu = url.split(['?', '#']).next().unwrap_or(url);
+        let mut claims = json!({
+            "jti": random_token(),
+            "htm": method,
+            "htu": htu,
+            "iat": Utc::now().timestamp(),
+        });
+        if let Some(nonce) = nonce {
+            claims["nonce"] = nonce.into();
+        }
+        if let Some(access_token) = access_token {
+            claims["ath"] = URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes())).into();
+        }
+
+        let message = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: p256::ecdsa::Signature = self.0.sign(message.as_bytes());
+        let signature = signature.normalize_s().unwrap_or(signature);
+        let token = format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()));
+        Ok(HeaderValue::from_str(&token).map_err(|e| anyhow!(e))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{extract::State, routing::post, Form, Json, Router};
+
+    use super::*;
+
+    /// A token endpoint that trades `r1` for `r2` once, after a pause so
+    /// concurrent refreshes overlap.
+    async fn token_endpoint(
+        State(calls): State<Arc<AtomicUsize>>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> (StatusCode, Json<serde_json::Value>) {
+        calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if form.get("refresh_token").map(String::as_str) != Some("r1") {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "invalid_grant" })));
+        }
+        let tokens = json!({
+            "access_token": "a2",
+            "token_type": "DPoP",
+            "expires_in": 3600,
+            "refresh_token": "r2",
+            "scope": SCOPE,
+            "sub": "did:plc:abc",
+        });
+        (StatusCode::OK, Json(tokens))
+    }
+
+    fn session(token_endpoint: &str) -> Session {
+        Session {
+            id: "session".to_string(),
+            did: "did:plc:abc".to_string(),
+            handle: "alice.test".to_string(),
+            pds_url: "http://127.0.0.1:9".to_string(),
+            issuer: "http://127.0.0.1:9".to_string(),
+            token_endpoint: token_endpoint.to_string(),
+            scope: SCOPE.to_string(),
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Utc::now(),
+            dpop_key: DpopKey::generate().encode(),
+            dpop_nonce: None,
+            pds_dpop_nonce: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_of_a_session_spend_its_token_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let router = Router::new().route("/token", post(token_endpoint)).with_state(calls.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = OAuthClient::new(reqwest::Client::new(), &ApiConfig::new("http://localhost:8000"));
+        let (mut first, mut second) = (session(&url), session(&url));
+        let (a, b) = tokio::join!(client.refresh(&mut first), client.refresh(&mut second));
+
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        for session in [first, second] {
+            assert_eq!(session.access_token, "a2");
+            assert_eq!(session.refresh_token.as_deref(), Some("r2"));
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::Cursor;
+
+use ab_glyph::{FontRef, PxScale};
+use anyhow::Context;
+use axum::body::Bytes;
+use blogi_db::{actor::Actor, entry::Entry};
+use blogi_errors::Result;
+use image::{imageops::{self, FilterType}, DynamicImage, ImageFormat, Rgba, RgbaImage};
+use imageproc::{drawing::{draw_filled_rect_mut, draw_text_mut, text_size}, rect::Rect};
+
+use crate::blobs::{BlobStore, Preset};
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+const MARGIN: i32 = 80;
+const ACCENT_WIDTH: u32 = 24;
+const AVATAR_SIZE: u32 = 112;
+const TITLE_SCALE: f32 = 68.0;
+const TITLE_LINE_HEIGHT: i32 = 84;
+const TITLE_MAX_LINES: usize = 3;
+
+const BACKGROUND: Rgba<u8> = Rgba([250, 248, 244, 255]);
+const ACCENT: Rgba<u8> = Rgba([88, 80, 236, 255]);
+const FOREGROUND: Rgba<u8> = Rgba([24, 24, 27, 255]);
+const MUTED: Rgba<u8> = Rgba([113, 113, 122, 255]);
+
+/// Rendered cards, keyed by the ETag of the entry and profile they were
+/// rendered from.
+pub type OgImageCache = moka::future::Cache<String, Bytes>;
+
+pub fn cache() -> OgImageCache {
+    moka::future::Cache::builder()
+        .weigher(|_, png: &Bytes| png.len().try_into().unwrap_or(u32::MAX))
+        .max_capacity(64 * 1024 * 1024)
+        .build()
+}
+
+/// The contents of an entry's social preview card.
+pub struct Card {
+    pub title: String,
+    pub author: String,
+    pub handle: String,
+    pub avatar: Option<DynamicImage>,
+}
+
+impl Card {
+    pub fn render_png(&self) -> anyhow::Result<Vec<u8>> {
+        let bold = FontRef::try_from_slice(notosans::BOLD_TTF)?;
+        let regular = FontRef::try_from_slice(notosans::REGULAR_TTF)?;
+
+        let mut canvas = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+        draw_filled_rect_mut(&mut canvas, Rect::at(0, 0).of_size(ACCENT_WIDTH, HEIGHT), ACCENT);
+
+        let text_width = WIDTH - 2 * MARGIN as u32;
+        let lines = wrap(&bold, PxScale::from(TITLE_SCALE), &self.title, text_width, TITLE_MAX_LINES);
+        for (i, line) in lines.iter().enumerate() {
+            let y = MARGIN + i as i32 * TITLE_LINE_HEIGHT;
+            draw_text_mut(&mut canvas, FOREGROUND, MARGIN, y, TITLE_SCALE, &bold, line);
+        }
+
+        let footer_y = HEIGHT as i32 - MARGIN - AVATAR_SIZE as i32;
+        let mut name_x = MARGIN;
+        if let Some(avatar) = &self.avatar {
+            imageops::overlay(&mut canvas, &circle(avatar, AVATAR_SIZE), MARGIN as i64, footer_y as i64);
+            name_x += AVATAR_SIZE as i32 + 32;
+        }
+
+        draw_text_mut(&mut canvas, FOREGROUND, name_x, footer_y + 12, 44.0, &bold, &self.author);
+        draw_text_mut(&mut canvas, MUTED, name_x, footer_y + 66, 32.0, &regular, &format!("@{}", self.handle));
+
+        let (site_width, _) = text_size(32.0, &bold, "Blogi");
+        let site_x = WIDTH as i32 - MARGIN - site_width as i32;
+        draw_text_mut(&mut canvas, ACCENT, site_x, footer_y + 66, 32.0, &bold, "Blogi");
+
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(canvas).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    }
+}
+
+/// Breaks text into at most `max_lines` lines that fit in `max_width`,
+/// ellipsizing the last line if the text doesn't fit. Words too wide for a
+/// line of their own are broken between characters.
+fn wrap(font: &FontRef, scale: PxScale, text: &str, max_width: u32, max_lines: usize) -> Vec<String> {
+    let fits = |line: &str| text_size(scale, font, line).0 <= max_width;
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace().flat_map(|word| break_word(fits, word)) {
+        let word = word.as_str();
+        let candidate = if current.is_empty() { word.to_string() } else { format!("{current} {word}") };
+        if fits(&candidate) || current.is_empty() {
+            current = candidate;
+            continue;
+        }
+
+        lines.push(std::mem::replace(&mut current, word.to_string()));
+        if lines.len() == max_lines {
+            // Out of room, so the last line needs to show it was cut short.
+            let mut last = lines.pop().unwrap_or_default();
+            while !last.is_empty() && !fits(&format!("{last}…")) {
+                last.pop();
+            }
+            lines.push(format!("{}…", last.trim_end()));
+            return lines;
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Splits a word into pieces that each fit, keeping as much as possible in
+/// each piece. Words that already fit come back whole.
+fn break_word(fits: impl Fn(&str) -> bool, word: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for c in word.chars() {
+        piece.push(c);
+        if !fits(&piece) && piece.chars().count() > 1 {
+            piece.pop();
+            pieces.push(std::mem::replace(&mut piece, c.to_string()));
+        }
+    }
+    pieces.push(piece);
+    pieces
+}
+
+/// Crops an image to a circle of the given diameter.
+fn circle(image: &DynamicImage, size: u32) -> RgbaImage {
+    let mut image = image.resize_to_fill(size, size, FilterType::Lanczos3).to_rgba8();
+    let radius = size as f32 / 2.0;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - radius, y as f32 + 0.5 - radius);
+        if dx * dx + dy * dy > radius * radius {
+            pixel.0[3] = 0;
+        }
+    }
+    image
+}
+
+async fn fetch_avatar(blobs: &BlobStore, author: &Actor) -> Result<Option<DynamicImage>> {
+    let (Some(pds), Some(cid)) = (&author.pds_endpoint, &author.avatar_cid) else {
+        return Ok(None);
+    };
+
+    let avatar = blobs.get_image(pds, &author.did, cid, Preset::Avatar).await?;
+    Ok(Some(image::load_from_memory(&avatar.bytes).map_err(anyhow::Error::from)?))
+}
+
+/// A rendered preview card.
+pub struct EntryCard {
+    pub png: Bytes,
+    /// Whether the author's avatar couldn't be fetched and was left out, so
+    /// the card is only worth keeping until it can be rendered again.
+    pub missing_avatar: bool,
+}
+
+/// Renders the preview card for an entry.
+pub async fn render_entry_card(blobs: &BlobStore, entry: &Entry, author: &Actor) -> Result<EntryCard> {
+    // A broken avatar shouldn't stop the card from rendering.
+    let (avatar, missing_avatar) = match fetch_avatar(blobs, author).await {
+        Ok(avatar) => (avatar, false),
+        Err(e) => {
+            tracing::warn!("failed to fetch avatar for {}: {e}", author.did);
+            (None, true)
+        }
+    };
+
+    let card = Card {
+        title: entry.title.clone(),
+        author: author.display_name.clone().filter(|n| !n.is_empty()).unwrap_or_else(|| author.handle.clone()),
+        handle: author.handle.clone(),
+        avatar,
+    };
+
+    let png = tokio::task::spawn_blocking(move || card.render_png())
+        .await
+        .context("card renderer panicked")??;
+
+    Ok(EntryCard { png: Bytes::from(png), missing_avatar })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlong_words_are_broken_to_fit() {
+        let font = FontRef::try_from_slice(notosans::BOLD_TTF).unwrap();
+        let scale = PxScale::from(TITLE_SCALE);
+        let title = format!("A {} title", "W".repeat(80));
+
+        let lines = wrap(&font, scale, &title, 1040, TITLE_MAX_LINES);
+
+        assert_eq!(lines.len(), TITLE_MAX_LINES);
+        assert!(lines.last().unwrap().ends_with('…'));
+        for line in &lines {
+            assert!(text_size(scale, &font, line).0 <= 1040, "{line:?} overflows");
+        }
+    }
+
+    #[test]
+    fn short_titles_are_untouched() {
+        let font = FontRef::try_from_slice(notosans::BOLD_TTF).unwrap();
+
+        let lines = wrap(&font, PxScale::from(TITLE_SCALE), "Hello  world", 1040, TITLE_MAX_LINES);
+
+        assert_eq!(lines, ["Hello world"]);
+    }
+}
@@ -0,0 +1,71 @@
+use atrium_api::types::LimitedNonZeroU8;
+use blogi_db::{
+    entry::{Entry, SearchResult},
+    like::Like,
+    pagination::{Cursor, SortKey},
+    series::Series,
+};
+use blogi_errors::Result;
+
+use crate::config::ApiConfig;
+
+pub const DEFAULT_LIMIT: u8 = 50;
+pub const MAX_LIMIT: u8 = 100;
+
+/// The page size to serve. Lexicons bound `limit` by `MAX_LIMIT`, so a
+/// requested size only needs a default.
+pub fn limit(requested: Option<LimitedNonZeroU8<MAX_LIMIT>>) -> usize {
+    requested.map_or(DEFAULT_LIMIT, u8::from).into()
+}
+
+/// Decodes a cursor a client got from one of the `next_*` functions.
+pub fn cursor(config: &ApiConfig, token: Option<&str>) -> Result<Option<Cursor>> {
+    token.map(|token| Cursor::decode(token, &config.secret_key)).transpose()
+}
+
+/// The cursor for the page after `entries`, if there might be one.
+pub fn next_cursor(config: &ApiConfig, entries: &[Entry], limit: usize) -> Option<String> {
+    if entries.len() < limit {
+        return None;
+    }
+    let entry = entries.last()?;
+    Some(Cursor::new(SortKey::Timestamp(entry.published_at()), &entry.uri).encode(&config.secret_key))
+}
+
+/// The cursor for the page after search `results`, if there might be one.
+pub fn next_search_cursor(config: &ApiConfig, results: &[SearchResult], limit: usize) -> Option<String> {
+    if results.len() < limit {
+        return None;
+    }
+    let result = results.last()?;
+    Some(Cursor::new(SortKey::Rank(result.rank), &result.entry.uri).encode(&config.secret_key))
+}
+
+/// The cursor for the page after `entries` ranked by comment count, if there
+/// might be one.
+pub fn next_popular_cursor(config: &ApiConfig, entries: &[Entry], limit: usize) -> Option<String> {
+    if entries.len() < limit {
+        return None;
+    }
+    let entry = entries.last()?;
+    Some(Cursor::new(SortKey::Count(entry.comment_count), &entry.uri).encode(&config.secret_key))
+}
+
+/// The cursor for the page after `likes`, if there might be one.
+pub fn next_like_cursor(config: &ApiConfig, likes: &[Like], limit: usize) -> Option<String> {
+    if likes.len() < limit {
+        return None;
+    }
+    let like = likes.last()?;
+    Some(Cursor::new(SortKey::Timestamp(like.created_at), &like.uri).encode(&config.secret_key))
+}
+
+/// The cursor for the page after `entries` of `series`, if there might be one.
+pub fn next_series_cursor(config: &ApiConfig, series: &Series, entries: &[Entry], limit: usize) -> Option<String> {
+    if entries.len() < limit {
+        return None;
+    }
+    let entry = entries.last()?;
+    let position = series.entries.iter().position(|series_entry| series_entry.uri == entry.uri)?;
+    Some(Cursor::new(SortKey::Count(position as i64), &entry.uri).encode(&config.secret_key))
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use blogi_db::entry::{MATCH_END, MATCH_START};
+use blogi_lexicons::moe::hayden::blogi::blog::defs::ImageView;
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use similar::{ChangeTag, TextDiff};
+
+/// The URL scheme content uses to refer to one of the entry's images.
+const BLOB_SCHEME: &str = "blob:";
+
+/// Unchanged lines shown around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// How long a diff may take before it settles for a coarser result.
+const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How an entry's `content` is meant to be interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFormat {
+    Markdown,
+    Plain,
+    Html,
+}
+
+impl ContentFormat {
+    /// Maps an entry's `contentFormat`. Entries from before the field
+    /// existed are Markdown, and anything we don't recognise is shown as
+    /// plain text rather than guessed at.
+    pub fn from_mime(mime: Option<&str>) -> ContentFormat {
+        match mime {
+            None | Some("text/markdown") => ContentFormat::Markdown,
+            Some("text/html") => ContentFormat::Html,
+            Some(_) => ContentFormat::Plain,
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            ContentFormat::Markdown => "text/markdown",
+            ContentFormat::Plain => "text/plain",
+            ContentFormat::Html => "text/html",
+        }
+    }
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+/// Renders entry content to sanitized HTML. Images pointing at
+/// `blob:<cid>` are swapped for the matching attached image's URL.
+pub fn content_to_html(content: &str, format: ContentFormat, images: &[ImageView]) -> String {
+    match format {
+        ContentFormat::Markdown => {
+            let mut unsafe_html = String::new();
+            html::push_html(&mut unsafe_html, Parser::new_ext(content, options()));
+            sanitize(&unsafe_html, images)
+        }
+        ContentFormat::Html => sanitize(content, images),
+        ContentFormat::Plain => content
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|paragraph| !paragraph.is_empty())
+            .map(|paragraph| format!("<p>{}</p>", escape(paragraph).replace('\n', "<br>\n")))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn sanitize(html: &str, images: &[ImageView]) -> String {
+    let urls: HashMap<String, String> = images
+        .iter()
+        .map(|image| (image.cid.as_ref().to_string(), image.fullsize.clone()))
+        .collect();
+
+    ammonia::Builder::default()
+        .add_url_schemes(["blob"])
+        .attribute_filter(move |element, attribute, value| {
+            match (element, attribute, value.strip_prefix(BLOB_SCHEME)) {
+                ("img", "src", Some(cid)) => urls.get(cid).map(|url| url.clone().into()),
+                (_, "src" | "href", Some(_)) => None,
+                _ => Some(value.into()),
+            }
+        })
+        .clean(html)
+        .to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Lists the CIDs of images that content places inline.
+pub fn inline_image_cids(content: &str, format: ContentFormat) -> HashSet<String> {
+    match format {
+        ContentFormat::Markdown => Parser::new_ext(content, options())
+            .filter_map(|event| match event {
+                Event::Start(Tag::Image { dest_url, .. }) => {
+                    dest_url.strip_prefix(BLOB_SCHEME).map(str::to_string)
+                }
+                _ => None,
+            })
+            .collect(),
+        ContentFormat::Html => {
+            let cids = Arc::new(Mutex::new(HashSet::new()));
+            let found = cids.clone();
+            ammonia::Builder::default()
+                .add_url_schemes(["blob"])
+                .attribute_filter(move |element, attribute, value| {
+                    if let ("img", "src", Some(cid)) = (element, attribute, value.strip_prefix(BLOB_SCHEME)) {
+                        found.lock().unwrap().insert(cid.to_string());
+                    }
+                    Some(value.into())
+                })
+                .clean(content);
+
+            cids.lock().unwrap().clone()
+        }
+        ContentFormat::Plain => HashSet::new(),
+    }
+}
+
+/// Produces a plain-text summary of entry content, cut off at `max_chars`
+/// characters on a word boundary.
+pub fn excerpt(content: &str, format: ContentFormat, max_chars: usize) -> String {
+    let text = match format {
+        ContentFormat::Markdown => {
+            let mut text = String::new();
+            for event in Parser::new_ext(content, options()) {
+                match event {
+                    Event::Text(t) | Event::Code(t) => text.push_str(&t),
+                    Event::SoftBreak | Event::HardBreak | Event::End(TagEnd::Paragraph | TagEnd::Heading(_)) => {
+                        text.push(' ')
+                    }
+                    _ => {}
+                }
+            }
+            text
+        }
+        ContentFormat::Html => ammonia::Builder::empty()
+            .clean_content_tags(["script", "style"].into())
+            .clean(content)
+            .to_string()
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&nbsp;", " ")
+            .replace("&amp;", "&"),
+        ContentFormat::Plain => content.to_string(),
+    };
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(' ') {
+        Some(idx) => &cut[..idx],
+        None => &cut,
+    };
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+/// Turns a search snippet into HTML, wrapping its matches in `<mark>`.
+pub fn highlight_snippet(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    let mut text = String::new();
+    let mut marked = false;
+    for c in snippet.chars() {
+        // Content could contain the markers itself, so unbalanced ones are dropped.
+        match c {
+            MATCH_START if !marked => {
+                html.push_str(&escape(&text));
+                html.push_str("<mark>");
+                text.clear();
+                marked = true;
+            }
+            MATCH_END if marked => {
+                html.push_str(&escape(&text));
+                html.push_str("</mark>");
+                text.clear();
+                marked = false;
+            }
+            MATCH_START | MATCH_END => {}
+            c if c.is_whitespace() => {
+                if !text.ends_with(' ') && !html.ends_with(' ') {
+                    text.push(' ');
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    html.push_str(&escape(&text));
+    if marked {
+        html.push_str("</mark>");
+    }
+    html.trim().to_string()
+}
+
+/// A run of changed lines and the unchanged lines around them.
+pub struct DiffHunk {
+    pub lines: Vec<DiffLine>,
+}
+
+pub struct DiffLine {
+    /// `"delete"`, `"insert"` or `"equal"`.
+    pub tag: &'static str,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    /// The line's text, with the parts that changed within it marked.
+    pub segments: Vec<(bool, String)>,
+}
+
+/// Diffs two versions of an entry's content line by line, highlighting
+/// the words that changed within each modified line.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::configure().timeout(DIFF_TIMEOUT).diff_lines(old, new);
+    diff.grouped_ops(DIFF_CONTEXT)
+        .iter()
+        .map(|group| DiffHunk {
+            lines: group
+                .iter()
+                .flat_map(|op| diff.iter_inline_changes(op))
+                .map(|change| DiffLine {
+                    tag: match change.tag() {
+                        ChangeTag::Delete => "delete",
+                        ChangeTag::Insert => "insert",
+                        ChangeTag::Equal => "equal",
+                    },
+                    old_line: change.old_index().map(|idx| idx + 1),
+                    new_line: change.new_index().map(|idx| idx + 1),
+                    segments: change
+                        .iter_strings_lossy()
+                        .map(|(emphasized, text)| (emphasized, text.trim_end_matches('\n').to_string()))
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
@@ -0,0 +1,81 @@
+use std::{sync::Arc, time::Duration};
+
+use blogi_db::Datastore;
+use blogi_errors::Success;
+use tokio::time::MissedTickBehavior;
+
+/// How often to check for scheduled entries that have gone live.
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// Publishes scheduled entries as their publish time passes. Feeds and pages
+/// decide visibility when they're read, so entries show up on time either
+/// way; this reindexes each one as it goes live, like the ingester does for
+/// an entry that arrives live, and announces the public ones once.
+pub async fn run(db: Arc<Box<dyn Datastore>>) {
+    let mut interval = tokio::time::interval(INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = publish(db.as_ref().as_ref()).await {
+            tracing::warn!("failed to announce scheduled entries: {e}");
+        }
+    }
+}
+
+async fn publish(db: &dyn Datastore) -> Success {
+    for uri in db.announce_published_entries().await? {
+        tracing::info!("scheduled entry {uri} went live");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use blogi_db::{graph::Follow, pg::PostgresDatastore, records};
+    use chrono::{TimeDelta, Utc};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::*;
+
+    const ALICE: &str = "did:plc:alice";
+    const BOB: &str = "did:plc:bob";
+    const URI: &str = "at://did:plc:alice/moe.hayden.blogi.blog.entry/3k";
+
+    #[sqlx::test(migrations = "../../../migrations")]
+    async fn entries_join_feeds_once_their_publish_time_passes(pool: PgPool) {
+        let db = PostgresDatastore(pool.clone()).boxed();
+        let db: &dyn Datastore = db.as_ref();
+        db.put_follow(&Follow {
+            uri: format!("at://{BOB}/app.bsky.graph.follow/3k"),
+            cid: "bafyfollow".to_string(),
+            did: BOB.to_string(),
+            rkey: "3k".to_string(),
+            subject: ALICE.to_string(),
+            created_at: Utc::now(),
+            indexed_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        let publish_at = (Utc::now() + TimeDelta::hours(1)).to_rfc3339();
+        let record = json!({ "title": "Soon", "content": "Not yet", "publishAt": publish_at });
+        let entry = records::entry(ALICE, URI, "bafyentry", serde_json::from_value(record).unwrap());
+        db.put_entry(&entry).await.unwrap();
+        assert!(db.list_following_entries(BOB, 10, None).await.unwrap().is_empty());
+
+        // Stand in for the hour passing.
+        sqlx::query("UPDATE entry SET publish_at = now() - interval '1 minute' WHERE uri = $1")
+            .bind(URI)
+            .execute(&pool)
+            .await
+            .unwrap();
+        publish(db).await.unwrap();
+
+        let feed = db.list_following_entries(BOB, 10, None).await.unwrap();
+        assert_eq!(feed.iter().map(|entry| entry.uri.as_str()).collect::<Vec<_>>(), [URI]);
+        assert!(feed[0].indexed_at > entry.indexed_at, "going live reindexes the entry");
+        assert!(db.announce_published_entries().await.unwrap().is_empty(), "it's only announced once");
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::{extract::OptionalFromRequestParts, http::request::Parts};
+use axum_extra::extract::CookieJar;
+use blogi_errors::{BlogiError, Result, Success};
+use chacha20poly1305::{aead::{Aead, KeyInit, Payload}, ChaCha20Poly1305, Nonce};
+use chrono::{DateTime, Utc};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::{auth::Viewer, state::AppState};
+
+/// The cookie holding a reader's session ID.
+pub const SESSION_COOKIE: &str = "blogi_session";
+
+/// How long a reader stays signed in without coming back. Matches how long
+/// authorization servers keep a public client's refresh tokens.
+pub const SESSION_TTL: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+const NONCE_LEN: usize = 12;
+
+/// Keeps sessions and in-flight sign-ins in Redis, encrypted with a key
+/// derived from `SECRET_KEY` so a leaked Redis dump doesn't leak tokens.
+pub struct SessionStore {
+    client: redis::Client,
+    redis: OnceCell<ConnectionManager>,
+    cipher: ChaCha20Poly1305,
+}
+
+impl SessionStore {
+    /// Opens the store, connecting to Redis up front so a bad `REDIS_URL`
+    /// stops the service starting.
+    pub async fn open(redis_url: &str, secret_key: &str) -> Result<SessionStore> {
+        let store = SessionStore::new(redis_url, secret_key)?;
+        store.redis().await?;
+        Ok(store)
+    }
+
+    /// Creates the store without connecting to Redis until it's first used.
+    pub fn new(redis_url: &str, secret_key: &str) -> Result<SessionStore> {
+        // `SECRET_KEY` keys other things too, so this key is derived apart
+        // from theirs.
+        let key = Sha256::new().chain_update(b"blogi session\0").chain_update(secret_key.as_bytes()).finalize();
+
+        Ok(SessionStore {
+            client: redis::Client::open(redis_url).map_err(|e| anyhow!(e))?,
+            redis: OnceCell::new(),
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    async fn redis(&self) -> Result<ConnectionManager> {
+        let redis = self
+            .redis
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .map_err(|e| anyhow!(e))?;
+        Ok(redis.clone())
+    }
+
+    pub async fn put<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) -> Success {
+        let plaintext = serde_json::to_vec(value).map_err(|e| anyhow!(e))?;
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        // Binding the ciphertext to its key stops one entry being swapped
+        // in for another.
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad: key.as_bytes() })
+            .map_err(|e| anyhow!(e))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        let _: () = self
+            .redis()
+            .await?
+            .set_ex(key, sealed, ttl.as_secs())
+            .await
+            .map_err(|e| anyhow!(e))?;
+        Ok(())
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let sealed: Option<Vec<u8>> = self.redis().await?.get(key).await.map_err(|e| anyhow!(e))?;
+        sealed.map(|sealed| self.open_sealed(key, &sealed)).transpose()
+    }
+
+    /// Gets and removes a value, so it can only be used once.
+    pub async fn take<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let sealed: Option<Vec<u8>> = self.redis().await?.get_del(key).await.map_err(|e| anyhow!(e))?;
+        sealed.map(|sealed| self.open_sealed(key, &sealed)).transpose()
+    }
+
+    pub async fn delete(&self, key: &str) -> Success {
+        let _: () = self.redis().await?.del(key).await.map_err(|e| anyhow!(e))?;
+        Ok(())
+    }
+
+    fn open_sealed<T: DeserializeOwned>(&self, key: &str, sealed: &[u8]) -> Result<T> {
+        let (nonce, ciphertext) = sealed
+            .split_at_checked(NONCE_LEN)
+            .ok_or_else(|| anyhow!("sealed value for {key} is truncated"))?;
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.as_bytes() })
+            .map_err(|_| anyhow!("sealed value for {key} doesn't decrypt; was SECRET_KEY changed?"))?;
+        Ok(serde_json::from_slice(&plaintext).map_err(|e| anyhow!(e))?)
+    }
+}
+
+/// The Redis key a session is stored under.
+pub fn session_key(id: &str) -> String {
+    format!("session:{id}")
+}
+
+/// A reader signed in through OAuth, along with the tokens their PDS issued.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    #[serde(skip)]
+    pub id: String,
+    pub did: String,
+    pub handle: String,
+    pub pds_url: String,
+    pub issuer: String,
+    pub token_endpoint: String,
+    pub scope: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    /// The private half of the DPoP key the tokens are bound to.
+    pub dpop_key: String,
+    /// The last DPoP nonce the authorization server handed out.
+    pub dpop_nonce: Option<String>,
+    /// The last DPoP nonce the PDS handed out, which is separate from the
+    /// authorization server's.
+    pub pds_dpop_nonce: Option<String>,
+}
+
+impl Session {
+    /// The signed-in reader, to check what pages can show them against.
+    pub fn viewer(&self) -> Viewer {
+        Viewer { did: self.did.clone() }
+    }
+}
+
+impl OptionalFromRequestParts<AppState> for Session {
+    type Rejection = BlogiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Option<Self>> {
+        let jar = CookieJar::from_headers(&parts.headers);
+        let Some(id) = jar.get(SESSION_COOKIE).map(|cookie| cookie.value().to_string()) else {
+            return Ok(None);
+        };
+
+        // A session that's expired or can't be read just means signing in
+        // again, not an error page.
+        match state.sessions.get::<Session>(&session_key(&id)).await {
+            Ok(session) => Ok(session.map(|session| Session { id, ..session })),
+            Err(e) => {
+                tracing::warn!("failed to load session: {e}");
+                Ok(None)
+            }
+        }
+    }
+}
@@ -1,6 +1,21 @@
 use std::sync::Arc;
 
+use blogi_identity::DidResolver;
+
+use crate::{
+    blobs::BlobStore, bsky::BskyClient, config::ApiConfig, identity::ServiceIdentity, oauth::OAuthClient, og::OgImageCache,
+    session::SessionStore,
+};
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: Arc<Box<dyn blogi_db::Datastore>>,
+    pub config: Arc<ApiConfig>,
+    pub blobs: Arc<BlobStore>,
+    pub bsky: Arc<BskyClient>,
+    pub did_resolver: Arc<DidResolver>,
+    pub identity: Arc<ServiceIdentity>,
+    pub oauth: Arc<OAuthClient>,
+    pub sessions: Arc<SessionStore>,
+    pub og_images: OgImageCache,
 }
@@ -0,0 +1,315 @@
+use askama::Template;
+use axum::response::Html;
+use blogi_db::entry::Entry;
+use blogi_errors::Result;
+use blogi_lexicons::moe::hayden::blogi::blog::defs::{
+    CommentView, ImageView, PostView, SeriesEntryLink, SeriesMembership,
+};
+use chrono::{SecondsFormat, Utc};
+
+use crate::{
+    config::ApiConfig,
+    meta::{author_name, content_format, published, PageMeta},
+    render::{content_to_html, excerpt, ContentFormat, DiffHunk},
+    session::Session,
+    views::rkey,
+};
+
+const SUMMARY_LENGTH: usize = 280;
+
+/// What listing pages and feeds show for each post.
+pub struct PostSummary {
+    pub url: String,
+    pub title: String,
+    pub author: String,
+    pub published: String,
+    pub published_date: String,
+    pub updated: String,
+    pub excerpt: String,
+    pub content: String,
+    pub tags: Vec<String>,
+}
+
+impl PostSummary {
+    pub fn new(config: &ApiConfig, post: &PostView) -> PostSummary {
+        let format = content_format(post);
+        let images = post.record.images.as_deref().unwrap_or_default();
+
+        PostSummary {
+            url: config.post_url(post.author.did.as_str(), rkey(&post.uri)),
+            title: post.record.title.clone(),
+            author: author_name(&post.author).to_string(),
+            published: published(post).as_str().to_string(),
+            published_date: published(post).as_ref().format("%B %-d, %Y").to_string(),
+            updated: post.record.updated_at.as_ref().unwrap_or(published(post)).as_str().to_string(),
+            excerpt: excerpt(&post.record.content, format, SUMMARY_LENGTH),
+            content: content_to_html(&post.record.content, format, images),
+            tags: post.record.tags.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// What post pages show for each comment.
+pub struct CommentSummary {
+    pub id: String,
+    pub author: String,
+    pub content: String,
+    pub created: String,
+    pub created_date: String,
+    /// Set for replies to the post's Bluesky announcement.
+    pub bsky_url: Option<String>,
+}
+
+impl CommentSummary {
+    pub fn new(comment: &CommentView) -> CommentSummary {
+        let author = &comment.author;
+        CommentSummary {
+            id: format!("comment-{}", rkey(&comment.uri)),
+            author: match (&author.display_name, &author.handle) {
+                (Some(name), _) if !name.is_empty() => name.clone(),
+                (_, Some(handle)) => format!("@{}", handle.as_str()),
+                _ => author.did.as_str().to_string(),
+            },
+            content: comment.content.clone(),
+            created: comment.created_at.as_str().to_string(),
+            created_date: comment.created_at.as_ref().format("%B %-d, %Y").to_string(),
+            bsky_url: (comment.source == "bluesky").then(|| {
+                format!("https://bsky.app/profile/{}/post/{}", author.did.as_str(), rkey(&comment.uri))
+            }),
+        }
+    }
+}
+
+/// The form signed-in readers comment with, refilled when posting fails.
+pub struct CommentForm {
+    pub action: String,
+    pub content: String,
+    pub error: Option<&'static str>,
+}
+
+/// A post's place in a series, with links to its neighbours.
+pub struct SeriesNav {
+    pub title: String,
+    pub position: i64,
+    pub total: i64,
+    pub prev: Option<SeriesLink>,
+    pub next: Option<SeriesLink>,
+}
+
+pub struct SeriesLink {
+    pub url: String,
+    pub title: String,
+}
+
+impl SeriesNav {
+    pub fn new(config: &ApiConfig, post: &PostView, membership: &SeriesMembership) -> SeriesNav {
+        // Series only link the author's own entries, so neighbours share the post's DID.
+        let link = |link: &SeriesEntryLink| SeriesLink {
+            url: config.post_url(post.author.did.as_str(), rkey(&link.uri)),
+            title: link.title.clone(),
+        };
+
+        SeriesNav {
+            title: membership.title.clone(),
+            position: membership.position,
+            total: membership.total,
+            prev: membership.prev.as_ref().map(link),
+            next: membership.next.as_ref().map(link),
+        }
+    }
+}
+
+/// Who's signed in, shown at the top of pages readers browse.
+pub struct AccountNav {
+    pub handle: Option<String>,
+    /// The page to come back to after signing in or out.
+    pub return_to: String,
+}
+
+impl AccountNav {
+    pub fn new(session: Option<&Session>, return_to: &str) -> AccountNav {
+        AccountNav {
+            handle: session.map(|session| session.handle.clone()),
+            return_to: return_to.to_string(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "post.html")]
+pub struct PostPage<'a> {
+    pub account: AccountNav,
+    pub meta: PageMeta,
+    pub post: &'a PostView,
+    pub content: String,
+    /// Each tag, with the URL of the author's posts under it.
+    pub tags: Vec<(String, String)>,
+    /// Attached images the content doesn't place inline.
+    pub gallery: Vec<&'a ImageView>,
+    pub series: Vec<SeriesNav>,
+    /// Where to read the post's Bluesky announcement and its replies.
+    pub bsky_url: Option<String>,
+    /// Set once the post has been edited.
+    pub revisions_url: Option<String>,
+    /// Set when the signed-in reader wrote the post.
+    pub edit_url: Option<String>,
+    pub comments: Vec<CommentSummary>,
+    /// The next page of comments, if there are more.
+    pub comments_next_url: Option<String>,
+    pub comment_form: CommentForm,
+}
+
+#[derive(Template)]
+#[template(path = "tag.html")]
+pub struct TagPage {
+    pub account: AccountNav,
+    pub tag: String,
+    pub author: Option<String>,
+    pub feed_url: String,
+    pub posts: Vec<PostSummary>,
+    pub next_url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "atom.xml")]
+pub struct AtomFeed {
+    pub title: String,
+    pub page_url: String,
+    pub feed_url: String,
+    pub updated: String,
+    pub posts: Vec<PostSummary>,
+}
+
+/// One version of a post in its revision history.
+pub struct VersionSummary {
+    pub cid: String,
+    pub title: String,
+    pub datetime: String,
+    pub date: String,
+    /// Where to see what changed when this version was replaced.
+    pub diff_url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "revisions.html")]
+pub struct RevisionsPage {
+    pub title: String,
+    pub post_url: String,
+    /// Newest first, starting with the current version.
+    pub versions: Vec<VersionSummary>,
+}
+
+#[derive(Template)]
+#[template(path = "diff.html")]
+pub struct DiffPage {
+    pub title: String,
+    pub post_url: String,
+    pub revisions_url: String,
+    pub from: VersionSummary,
+    pub to: VersionSummary,
+    pub title_changed: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Template)]
+#[template(path = "login.html")]
+pub struct LoginPage {
+    pub handle: String,
+    pub return_to: String,
+    pub error: Option<&'static str>,
+}
+
+/// One of an author's entries in the editor's list.
+pub struct EditorEntry {
+    pub title: String,
+    pub status: &'static str,
+    pub unlisted: bool,
+    pub datetime: String,
+    pub date: String,
+    pub edit_url: String,
+    pub view_url: String,
+}
+
+impl EditorEntry {
+    pub fn new(config: &ApiConfig, entry: &Entry) -> EditorEntry {
+        let published_at = entry.published_at();
+        EditorEntry {
+            title: entry.title.clone(),
+            status: match entry.status.as_deref() {
+                Some("draft") => "Draft",
+                _ if published_at > Utc::now() => "Scheduled",
+                _ => "Published",
+            },
+            unlisted: entry.visibility.as_deref() == Some("unlisted"),
+            datetime: published_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            date: published_at.format("%B %-d, %Y").to_string(),
+            edit_url: format!("/editor/{}", entry.rkey),
+            view_url: config.post_url(&entry.did, &entry.rkey),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "editor_list.html")]
+pub struct EditorListPage {
+    pub account: AccountNav,
+    pub entries: Vec<EditorEntry>,
+    pub next_url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "editor.html")]
+pub struct EditorPage {
+    pub account: AccountNav,
+    /// Where the form posts to.
+    pub action: String,
+    pub title: String,
+    pub content: String,
+    pub content_format: String,
+    /// Comma-separated.
+    pub tags: String,
+    pub unlisted: bool,
+    /// Whether the entry is already published, rather than new or a draft.
+    pub live: bool,
+    /// The CID of the version being edited, so saving can't clobber changes
+    /// made elsewhere.
+    pub swap_cid: Option<String>,
+    pub view_url: Option<String>,
+    pub preview: Option<String>,
+    pub notice: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+impl EditorPage {
+    pub fn new(session: &Session, action: &str) -> EditorPage {
+        EditorPage {
+            account: AccountNav::new(Some(session), action),
+            action: action.to_string(),
+            title: String::new(),
+            content: String::new(),
+            content_format: ContentFormat::Markdown.mime().to_string(),
+            tags: String::new(),
+            unlisted: false,
+            live: false,
+            swap_cid: None,
+            view_url: None,
+            preview: None,
+            notice: None,
+            error: None,
+        }
+    }
+}
+
+/// The card markup handed out to other sites through oEmbed.
+#[derive(Template)]
+#[template(path = "embed.html")]
+pub struct PostEmbed<'a> {
+    pub post: &'a PostView,
+    pub url: &'a str,
+    pub author: &'a str,
+    pub excerpt: String,
+}
+
+pub fn render(template: &impl Template) -> Result<Html<String>> {
+    Ok(Html(template.render().map_err(anyhow::Error::from)?))
+}
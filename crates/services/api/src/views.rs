@@ -0,0 +1,394 @@
+use std::{collections::HashMap, num::NonZeroU64, str::FromStr};
+
+use anyhow::anyhow;
+use atrium_api::types::string::{Cid, Datetime, Did, Handle};
+use blogi_db::{
+    actor::{Actor, INVALID_HANDLE},
+    comment::Comment,
+    entry::{Entry, EntryImage, EntryRevision},
+    graph::FollowCounts,
+    like::Like,
+    pagination::{Cursor, SortKey},
+    series::{self, Series},
+    Datastore,
+};
+use blogi_errors::Result;
+use blogi_lexicons::{
+    com::atproto::repo::strong_ref,
+    moe::hayden::blogi::{
+        actor::defs::{ProfileView, ProfileViewData, ProfileViewDetailed, ProfileViewDetailedData},
+        blog::{
+            defs::{
+                AspectRatioData, CommentAuthorData, CommentView, CommentViewData, ImageView, ImageViewData,
+                PostView, PostViewData, PostViewRecordData, SeriesEntryLinkData, SeriesMembership,
+                SeriesMembershipData, SeriesView, SeriesViewData, ViewerStateData,
+            },
+            get_entry_revisions, get_likes,
+        },
+    },
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    auth::Viewer,
+    blobs::Preset,
+    bsky::{BskyClient, Reply},
+    config::ApiConfig,
+    render::ContentFormat,
+};
+
+fn datetime(value: DateTime<Utc>) -> Datetime {
+    Datetime::new(value.fixed_offset())
+}
+
+pub fn profile_view(config: &ApiConfig, actor: &Actor) -> Result<ProfileView> {
+    Ok(ProfileViewData {
+        avatar: actor.avatar_cid.as_ref().map(|cid| config.blob_url(Preset::Avatar, &actor.did, cid)),
+        banner: actor.banner_cid.as_ref().map(|cid| config.blob_url(Preset::Banner, &actor.did, cid)),
+        created_at: actor.created_at.map(datetime),
+        description: actor.description.clone(),
+        did: Did::new(actor.did.clone()).map_err(|e| anyhow!(e))?,
+        display_name: actor.display_name.clone(),
+        handle: Handle::new(actor.handle.clone()).map_err(|e| anyhow!(e))?,
+        indexed_at: datetime(actor.indexed_at),
+        posts_count: actor.posts_count,
+    }
+    .into())
+}
+
+pub fn profile_view_detailed(config: &ApiConfig, actor: &Actor, counts: &FollowCounts) -> Result<ProfileViewDetailed> {
+    Ok(ProfileViewDetailedData {
+        avatar: actor.avatar_cid.as_ref().map(|cid| config.blob_url(Preset::Avatar, &actor.did, cid)),
+        banner: actor.banner_cid.as_ref().map(|cid| config.blob_url(Preset::Banner, &actor.did, cid)),
+        created_at: actor.created_at.map(datetime),
+        description: actor.description.clone(),
+        did: Did::new(actor.did.clone()).map_err(|e| anyhow!(e))?,
+        display_name: actor.display_name.clone(),
+        followers_count: Some(counts.followers),
+        follows_count: Some(counts.follows),
+        handle: Handle::new(actor.handle.clone()).map_err(|e| anyhow!(e))?,
+        indexed_at: datetime(actor.indexed_at),
+        posts_count: actor.posts_count,
+    }
+    .into())
+}
+
+pub fn image_view(config: &ApiConfig, did: &str, image: &EntryImage) -> Result<ImageView> {
+    let aspect_ratio = image.aspect_width.zip(image.aspect_height).and_then(|(width, height)| {
+        Some(AspectRatioData {
+            width: NonZeroU64::new(width.try_into().ok()?)?,
+            height: NonZeroU64::new(height.try_into().ok()?)?,
+        }.into())
+    });
+
+    Ok(ImageViewData {
+        alt: image.alt.clone(),
+        aspect_ratio,
+        cid: Cid::from_str(&image.cid).map_err(|e| anyhow!(e))?,
+        fullsize: config.blob_url(Preset::Fullsize, did, &image.cid),
+        thumb: config.blob_url(Preset::Thumbnail, did, &image.cid),
+    }
+    .into())
+}
+
+pub fn post_view(config: &ApiConfig, entry: &Entry, author: &Actor) -> Result<PostView> {
+    let images = entry
+        .images
+        .iter()
+        .map(|image| image_view(config, &entry.did, image))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(PostViewData {
+        author: profile_view(config, author)?,
+        cid: Cid::from_str(&entry.cid).map_err(|e| anyhow!(e))?,
+        comment_count: Some(entry.comment_count),
+        indexed_at: datetime(entry.indexed_at),
+        like_count: Some(entry.like_count),
+        record: PostViewRecordData {
+            bsky_post_ref: bsky_post_ref(entry),
+            content: entry.content.clone(),
+            content_format: ContentFormat::from_mime(entry.content_format.as_deref()).mime().to_string(),
+            created_at: datetime(entry.created_at),
+            images: (!images.is_empty()).then_some(images),
+            publish_at: entry.publish_at.map(datetime),
+            status: entry.status.clone(),
+            tags: (!entry.tags.is_empty()).then(|| entry.tags.clone()),
+            title: entry.title.clone(),
+            updated_at: entry.updated_at.map(datetime),
+            visibility: entry.visibility.clone(),
+        }
+        .into(),
+        series: None,
+        uri: entry.uri.clone(),
+        viewer: None,
+    }
+    .into())
+}
+
+fn bsky_post_ref(entry: &Entry) -> Option<strong_ref::Main> {
+    let (uri, cid) = entry.bsky_post_uri.as_ref().zip(entry.bsky_post_cid.as_ref())?;
+    Some(strong_ref::MainData { cid: Cid::from_str(cid).ok()?, uri: uri.clone() }.into())
+}
+
+/// Builds the view of a post shown on its own, which also says where it sits
+/// in any series.
+pub async fn post_view_detailed(
+    db: &dyn Datastore,
+    config: &ApiConfig,
+    viewer: Option<&Viewer>,
+    entry: &Entry,
+    author: &Actor,
+) -> Result<PostView> {
+    let mut post = post_view(config, entry, author)?;
+    let series: Vec<_> = db
+        .list_series_memberships(entry, Viewer::did_of(viewer))
+        .await?
+        .into_iter()
+        .map(series_membership_view)
+        .collect();
+    post.series = (!series.is_empty()).then_some(series);
+    Ok(post)
+}
+
+fn series_membership_view(membership: series::SeriesMembership) -> SeriesMembership {
+    let link = |uri: Option<String>, title: Option<String>| {
+        uri.zip(title).map(|(uri, title)| SeriesEntryLinkData { title, uri }.into())
+    };
+
+    SeriesMembershipData {
+        next: link(membership.next_uri, membership.next_title),
+        position: membership.position,
+        prev: link(membership.prev_uri, membership.prev_title),
+        title: membership.series_title,
+        total: membership.total,
+        uri: membership.series_uri,
+    }
+    .into()
+}
+
+pub fn series_view(config: &ApiConfig, series: &Series, author: &Actor, entry_count: i64) -> Result<SeriesView> {
+    Ok(SeriesViewData {
+        author: profile_view(config, author)?,
+        cid: Cid::from_str(&series.cid).map_err(|e| anyhow!(e))?,
+        created_at: series.created_at.map(datetime),
+        description: series.description.clone(),
+        entry_count,
+        indexed_at: datetime(series.indexed_at),
+        title: series.title.clone(),
+        uri: series.uri.clone(),
+    }
+    .into())
+}
+
+/// Looks up the authors of a batch of entries at once, keyed by DID.
+pub async fn authors<'a>(db: &dyn Datastore, dids: impl Iterator<Item = &'a str>) -> Result<HashMap<String, Actor>> {
+    let mut dids: Vec<String> = dids.map(str::to_string).collect();
+    dids.sort();
+    dids.dedup();
+
+    Ok(db
+        .get_actors(&dids)
+        .await?
+        .into_iter()
+        .map(|actor| (actor.did.clone(), actor))
+        .collect())
+}
+
+/// Builds views for a page of entries, looking up all of their authors at
+/// once. Entries by authors we haven't indexed are left out.
+pub async fn post_views(
+    db: &dyn Datastore,
+    config: &ApiConfig,
+    viewer: Option<&Viewer>,
+    entries: &[Entry],
+) -> Result<Vec<PostView>> {
+    let authors = authors(db, entries.iter().map(|entry| entry.did.as_str())).await?;
+
+    let mut posts = entries
+        .iter()
+        .filter_map(|entry| authors.get(&entry.did).map(|author| post_view(config, entry, author)))
+        .collect::<Result<Vec<_>>>()?;
+    apply_viewer_state(db, viewer, &mut posts).await?;
+    Ok(posts)
+}
+
+pub fn comment_view(config: &ApiConfig, comment: &Comment, author: Option<&Actor>) -> Result<CommentView> {
+    Ok(CommentViewData {
+        author: CommentAuthorData {
+            avatar: author.and_then(|author| {
+                let cid = author.avatar_cid.as_ref()?;
+                Some(config.blob_url(Preset::Avatar, &author.did, cid))
+            }),
+            did: Did::new(comment.did.clone()).map_err(|e| anyhow!(e))?,
+            display_name: author.and_then(|author| author.display_name.clone()),
+            handle: author.map(|author| Handle::new(author.handle.clone()).map_err(|e| anyhow!(e))).transpose()?,
+        }
+        .into(),
+        cid: Cid::from_str(&comment.cid).map_err(|e| anyhow!(e))?,
+        content: comment.content.clone(),
+        created_at: datetime(comment.created_at),
+        parent: None,
+        source: "blogi".to_string(),
+        uri: comment.uri.clone(),
+    }
+    .into())
+}
+
+/// A page of comments on an entry, and the cursor for the next one.
+pub struct CommentPage {
+    pub comments: Vec<CommentView>,
+    pub cursor: Option<String>,
+}
+
+/// Lists comments on an entry oldest first, merging in replies to its
+/// Bluesky announcement. Replies are paged along with our own comments, by
+/// when they were written.
+pub async fn comment_views(
+    db: &dyn Datastore,
+    config: &ApiConfig,
+    bsky: &BskyClient,
+    entry: &Entry,
+    limit: usize,
+    cursor: Option<&Cursor>,
+) -> Result<CommentPage> {
+    let after = cursor.map(Cursor::timestamp).transpose()?;
+    let comments = db.list_comments(&entry.uri, limit as i64, cursor).await?;
+    let mut more = comments.len() >= limit;
+    let authors = authors(db, comments.iter().map(|comment| comment.did.as_str())).await?;
+    let mut merged = comments
+        .iter()
+        .map(|comment| {
+            let view = comment_view(config, comment, authors.get(&comment.did))?;
+            Ok((comment.created_at, comment.uri.clone(), view))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Bluesky being unreachable shouldn't hide the comments we have.
+    if let Some(bsky_post_uri) = &entry.bsky_post_uri {
+        match bsky.replies(bsky_post_uri).await {
+            Ok(replies) => {
+                let unseen = replies.iter().filter(|reply| {
+                    after.is_none_or(|(created_at, uri)| (reply.created_at, reply.uri.as_str()) > (created_at, uri))
+                });
+                // Nor should one reply the AppView returned something odd for.
+                for reply in unseen {
+                    match bsky_reply_view(reply) {
+                        Ok(view) => merged.push((reply.created_at, reply.uri.clone(), view)),
+                        Err(e) => tracing::warn!("skipping Bluesky reply {}: {e}", reply.uri),
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("failed to fetch Bluesky replies to {bsky_post_uri}: {e}"),
+        }
+    }
+    merged.sort_by(|(a_created_at, a_uri, _), (b_created_at, b_uri, _)| {
+        (a_created_at, a_uri).cmp(&(b_created_at, b_uri))
+    });
+    if merged.len() > limit {
+        merged.truncate(limit);
+        more = true;
+    }
+
+    let cursor = merged
+        .last()
+        .filter(|_| more)
+        .map(|(created_at, uri, _)| Cursor::new(SortKey::Timestamp(*created_at), uri).encode(&config.secret_key));
+    Ok(CommentPage {
+        comments: merged.into_iter().map(|(_, _, view)| view).collect(),
+        cursor,
+    })
+}
+
+pub fn bsky_reply_view(reply: &Reply) -> Result<CommentView> {
+    Ok(CommentViewData {
+        author: CommentAuthorData {
+            // Bluesky repliers rarely have an actor here for the blob proxy
+            // to find their PDS by, so they keep the AppView's CDN avatar.
+            avatar: reply.author.avatar.clone(),
+            did: Did::new(reply.author.did.clone()).map_err(|e| anyhow!(e))?,
+            display_name: reply.author.display_name.clone(),
+            handle: Handle::new(reply.author.handle.clone()).ok(),
+        }
+        .into(),
+        cid: Cid::from_str(&reply.cid).map_err(|e| anyhow!(e))?,
+        content: reply.text.clone(),
+        created_at: datetime(reply.created_at),
+        parent: reply.parent.clone(),
+        source: "bluesky".to_string(),
+        uri: reply.uri.clone(),
+    }
+    .into())
+}
+
+/// Views an account we only know by its DID, for likers whose profile
+/// hasn't been indexed yet.
+pub fn did_profile_view(did: &str, indexed_at: DateTime<Utc>) -> Result<ProfileView> {
+    Ok(ProfileViewData {
+        avatar: None,
+        banner: None,
+        created_at: None,
+        description: None,
+        did: Did::new(did.to_string()).map_err(|e| anyhow!(e))?,
+        display_name: None,
+        handle: Handle::new(INVALID_HANDLE.to_string()).map_err(|e| anyhow!(e))?,
+        indexed_at: datetime(indexed_at),
+        posts_count: 0,
+    }
+    .into())
+}
+
+pub fn like_view(config: &ApiConfig, like: &Like, actor: Option<&Actor>) -> Result<get_likes::Like> {
+    Ok(get_likes::LikeData {
+        actor: match actor {
+            Some(actor) => profile_view(config, actor)?,
+            None => did_profile_view(&like.did, like.indexed_at)?,
+        },
+        created_at: datetime(like.created_at),
+        indexed_at: datetime(like.indexed_at),
+    }
+    .into())
+}
+
+pub fn revision_view(revision: &EntryRevision) -> Result<get_entry_revisions::Revision> {
+    Ok(get_entry_revisions::RevisionData {
+        cid: Cid::from_str(&revision.cid).map_err(|e| anyhow!(e))?,
+        content: revision.content.clone(),
+        content_format: ContentFormat::from_mime(revision.content_format.as_deref()).mime().to_string(),
+        indexed_at: datetime(revision.indexed_at),
+        replaced_at: datetime(revision.replaced_at),
+        title: revision.title.clone(),
+        updated_at: revision.updated_at.map(datetime),
+    }
+    .into())
+}
+
+/// Fills in how the requesting account relates to each post, if there is one.
+pub async fn apply_viewer_state<'a>(
+    db: &dyn Datastore,
+    viewer: Option<&Viewer>,
+    posts: impl IntoIterator<Item = &'a mut PostView>,
+) -> Result<()> {
+    let Some(viewer) = viewer else {
+        return Ok(());
+    };
+
+    let mut posts: Vec<_> = posts.into_iter().collect();
+    let uris: Vec<String> = posts.iter().map(|post| post.uri.clone()).collect();
+    let likes: HashMap<String, String> = db.list_viewer_likes(&viewer.did, &uris).await?.into_iter().collect();
+
+    for post in posts.iter_mut() {
+        post.viewer = Some(ViewerStateData { like: likes.get(&post.uri).cloned() }.into());
+    }
+    Ok(())
+}
+
+/// The DID of a record's repo, from the start of its AT URI.
+pub fn did(uri: &str) -> &str {
+    let uri = uri.strip_prefix("at://").unwrap_or(uri);
+    uri.split('/').next().unwrap_or(uri)
+}
+
+/// The record key of an entry, from the end of its AT URI.
+pub fn rkey(uri: &str) -> &str {
+    uri.rsplit('/').next().unwrap_or(uri)
+}
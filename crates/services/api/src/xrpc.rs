@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use blogi_errors::{BlogiError, Result};
+use blogi_lexicons::{
+    moe::hayden::blogi::{
+        actor::get_profile,
+        blog::{
+            get_comments, get_entries_by_tag, get_entries_for_author, get_entry_revisions, get_following_feed, get_likes, get_series,
+            get_timeline, search_entries,
+        },
+    },
+    server::XrpcServer,
+};
+
+use crate::{auth::Viewer, handlers, state::AppState};
+
+/// Serves our lexicons' XRPC methods. Anything not listed here answers
+/// `MethodNotImplemented`.
+#[async_trait]
+impl XrpcServer for AppState {
+    type Context = Option<Viewer>;
+    type Error = BlogiError;
+
+    fn invalid_request(message: String) -> BlogiError {
+        BlogiError::InvalidRequest(message)
+    }
+
+    fn method_not_implemented(nsid: &'static str) -> BlogiError {
+        BlogiError::MethodNotImplemented(nsid.to_string())
+    }
+
+    async fn actor_get_profile(
+        &self,
+        _viewer: Option<Viewer>,
+        params: get_profile::Parameters,
+    ) -> Result<get_profile::Output> {
+        handlers::actor::get_profile(self, params).await
+    }
+
+    async fn blog_get_comments(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_comments::Parameters,
+    ) -> Result<get_comments::Output> {
+        handlers::blog::get_comments(self, viewer, params).await
+    }
+
+    async fn blog_get_entries_by_tag(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_entries_by_tag::Parameters,
+    ) -> Result<get_entries_by_tag::Output> {
+        handlers::blog::get_entries_by_tag(self, viewer, params).await
+    }
+
+    async fn blog_get_entries_for_author(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_entries_for_author::Parameters,
+    ) -> Result<get_entries_for_author::Output> {
+        handlers::blog::get_entries_for_author(self, viewer, params).await
+    }
+
+    async fn blog_get_entry_revisions(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_entry_revisions::Parameters,
+    ) -> Result<get_entry_revisions::Output> {
+        handlers::blog::get_entry_revisions(self, viewer, params).await
+    }
+
+    async fn blog_get_following_feed(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_following_feed::Parameters,
+    ) -> Result<get_following_feed::Output> {
+        let viewer = viewer.ok_or(BlogiError::AuthRequired)?;
+        handlers::blog::get_following_feed(self, viewer, params).await
+    }
+
+    async fn blog_get_likes(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_likes::Parameters,
+    ) -> Result<get_likes::Output> {
+        handlers::blog::get_likes(self, viewer, params).await
+    }
+
+    async fn blog_get_series(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_series::Parameters,
+    ) -> Result<get_series::Output> {
+        handlers::blog::get_series(self, viewer, params).await
+    }
+
+    async fn blog_get_timeline(
+        &self,
+        viewer: Option<Viewer>,
+        params: get_timeline::Parameters,
+    ) -> Result<get_timeline::Output> {
+        handlers::blog::get_timeline(self, viewer, params).await
+    }
+
+    async fn blog_search_entries(
+        &self,
+        viewer: Option<Viewer>,
+        params: search_entries::Parameters,
+    ) -> Result<search_entries::Output> {
+        handlers::blog::search_entries(self, viewer, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use blogi_db::{pg::PostgresDatastore, Datastore};
+    use blogi_identity::DidResolver;
+    use blogi_lexicons::server::{router, NSIDS};
+    use sqlx::postgres::PgPoolOptions;
+    use tower::ServiceExt;
+
+    use crate::{
+        blobs::BlobStore, bsky::BskyClient, config::ApiConfig, identity::ServiceIdentity,
+        oauth::OAuthClient, og, session::SessionStore, state::AppState,
+    };
+
+    /// Nothing listens on the discard port, so whatever a method reaches for
+    /// fails quickly instead of answering.
+    const NOWHERE: &str = "127.0.0.1:9";
+
+    async fn state() -> AppState {
+        let config = ApiConfig {
+            blob_cache_dir: std::env::temp_dir().join("blogi-xrpc-tests"),
+            bsky_appview_url: format!("http://{NOWHERE}"),
+            plc_directory_url: format!("http://{NOWHERE}"),
+            secret_key: "secret".to_string(),
+            redis_url: format!("redis://{NOWHERE}"),
+            ..ApiConfig::new("http://localhost:8000")
+        };
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy(&format!("postgres://blogi@{NOWHERE}/blogi"))
+            .unwrap();
+        let http = reqwest::Client::new();
+
+        AppState {
+            db: Arc::new(PostgresDatastore(pool).boxed()),
+            blobs: Arc::new(BlobStore::open(http.clone(), &config.blob_cache_dir, 0).await.unwrap()),
+            bsky: Arc::new(BskyClient::new(http.clone(), &config.bsky_appview_url)),
+            did_resolver: Arc::new(DidResolver::new(http.clone(), &config.plc_directory_url, &config.bsky_appview_url)),
+            identity: Arc::new(ServiceIdentity::new(&config).unwrap()),
+            oauth: Arc::new(OAuthClient::new(http, &config)),
+            sessions: Arc::new(SessionStore::new(&config.redis_url, &config.secret_key).unwrap()),
+            og_images: og::cache(),
+            config: Arc::new(config),
+        }
+    }
+
+    /// Parameters that pass a method's validation, so a request reaches the
+    /// method itself.
+    fn params(nsid: &str) -> &'static str {
+        match nsid {
+            "moe.hayden.blogi.actor.getProfile" => "actor=did:plc:abc",
+            "moe.hayden.blogi.blog.getComments"
+            | "moe.hayden.blogi.blog.getEntryRevisions"
+            | "moe.hayden.blogi.blog.getLikes" => "uri=at://did:plc:abc/moe.hayden.blogi.blog.entry/3k",
+            "moe.hayden.blogi.blog.getEntriesByTag" => "tag=rust",
+            "moe.hayden.blogi.blog.getEntriesForAuthor" => "authorDid=did:plc:abc",
+            "moe.hayden.blogi.blog.getFollowingFeed" | "moe.hayden.blogi.blog.getTimeline" => "",
+            "moe.hayden.blogi.blog.getSeries" => "uri=at://did:plc:abc/moe.hayden.blogi.blog.series/3k",
+            "moe.hayden.blogi.blog.searchEntries" => "q=rust",
+            nsid => panic!("{nsid} has no parameters to test it with; add some here"),
+        }
+    }
+
+    #[tokio::test]
+    async fn every_method_is_implemented() {
+        let router = router::<AppState>().with_state(state().await);
+
+        for nsid in NSIDS {
+            let request = Request::get(format!("/xrpc/{nsid}?{}", params(nsid))).body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+            assert_ne!(status, StatusCode::NOT_IMPLEMENTED, "{nsid} isn't implemented");
+            assert_ne!(status, StatusCode::BAD_REQUEST, "{nsid} rejected its parameters: {body:?}");
+        }
+    }
+}
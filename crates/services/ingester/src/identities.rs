@@ -0,0 +1,61 @@
+//! Gives every account the ingester indexes an actor to show it by.
+
+use std::{collections::HashSet, sync::Mutex};
+
+use blogi_db::{actor::INVALID_HANDLE, Datastore};
+use blogi_errors::{Result, Success};
+use blogi_identity::DidResolver;
+
+/// Resolves the handles and PDSes of accounts as their commits arrive.
+pub struct Identities {
+    resolver: DidResolver,
+    /// Accounts whose identity is already indexed, to save looking them up
+    /// again for every commit.
+    known: Mutex<HashSet<String>>,
+}
+
+impl Identities {
+    pub fn new(resolver: DidResolver) -> Identities {
+        Identities {
+            resolver,
+            known: Mutex::default(),
+        }
+    }
+
+    /// Makes sure `did` has an actor with its handle and PDS, resolving
+    /// them the first time the account is seen. An identity that doesn't
+    /// resolve is logged and tried again on the account's next commit.
+    pub async fn ensure(&self, db: &dyn Datastore, did: &str) -> Success {
+        if self.known.lock().unwrap_or_else(|e| e.into_inner()).contains(did) {
+            return Ok(());
+        }
+
+        let indexed = db.get_actor(did).await?.is_some_and(|actor| actor.pds_endpoint.is_some());
+        if !indexed {
+            match self.resolve(did).await {
+                Ok((handle, pds_endpoint)) => db.put_actor_identity(did, &handle, &pds_endpoint).await?,
+                Err(e) => {
+                    tracing::warn!("failed to resolve {did}: {e}");
+                    return Ok(());
+                }
+            }
+        }
+
+        self.known.lock().unwrap_or_else(|e| e.into_inner()).insert(did.to_string());
+        Ok(())
+    }
+
+    async fn resolve(&self, did: &str) -> Result<(String, String)> {
+        let document = self.resolver.resolve(did).await?;
+        let pds_endpoint = document.pds_endpoint()?.to_string();
+
+        // A handle only counts if it resolves back to the account.
+        let handle = match document.handle() {
+            Some(handle) if self.resolver.resolve_handle(handle).await.is_ok_and(|resolved| resolved == did) => {
+                handle.to_ascii_lowercase()
+            }
+            _ => INVALID_HANDLE.to_string(),
+        };
+        Ok((handle, pds_endpoint))
+    }
+}
@@ -0,0 +1,167 @@
+use blogi_db::{
+    records::{
+        self, COMMENT_COLLECTION, ENTRY_COLLECTION, FOLLOW_COLLECTION, LIKE_COLLECTION, PROFILE_COLLECTION,
+        SERIES_COLLECTION,
+    },
+    Datastore,
+};
+use blogi_errors::Success;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    identities::Identities,
+    jetstream::{Commit, Operation},
+};
+
+/// The collections the ingester indexes.
+pub const COLLECTIONS: &[&str] = &[
+    COMMENT_COLLECTION,
+    ENTRY_COLLECTION,
+    FOLLOW_COLLECTION,
+    LIKE_COLLECTION,
+    PROFILE_COLLECTION,
+    SERIES_COLLECTION,
+];
+
+/// The record key of an account's profile, which is its only valid one.
+const PROFILE_RKEY: &str = "self";
+
+/// Applies a commit to the index. Repos can hold anything, so records that
+/// don't match their lexicon are logged and skipped; only failing to write
+/// to the database is an error.
+pub async fn index(db: &dyn Datastore, identities: &Identities, did: &str, commit: Commit) -> Success {
+    let uri = format!("at://{did}/{}/{}", commit.collection, commit.rkey);
+
+    if commit.operation == Operation::Delete {
+        return match commit.collection.as_str() {
+            COMMENT_COLLECTION => db.delete_comment(&uri).await,
+            ENTRY_COLLECTION => db.delete_entry(&uri).await,
+            FOLLOW_COLLECTION => db.delete_follow(&uri).await,
+            LIKE_COLLECTION => db.delete_like(&uri).await,
+            PROFILE_COLLECTION if commit.rkey == PROFILE_RKEY => db.delete_profile(did).await,
+            SERIES_COLLECTION => db.delete_series(&uri).await,
+            _ => Ok(()),
+        };
+    }
+
+    let (Some(record), Some(cid)) = (commit.record, commit.cid) else {
+        tracing::warn!("skipping {uri}, which was written without a record");
+        return Ok(());
+    };
+    identities.ensure(db, did).await?;
+    match commit.collection.as_str() {
+        COMMENT_COLLECTION => match parse(&uri, record) {
+            Some(record) => db.put_comment(&records::comment(did, &uri, &cid, record)).await,
+            None => Ok(()),
+        },
+        ENTRY_COLLECTION => match parse(&uri, record) {
+            Some(record) => db.put_entry(&records::entry(did, &uri, &cid, record)).await,
+            None => Ok(()),
+        },
+        FOLLOW_COLLECTION => match parse(&uri, record) {
+            Some(record) => db.put_follow(&records::follow(did, &uri, &cid, record)).await,
+            None => Ok(()),
+        },
+        LIKE_COLLECTION => match parse(&uri, record) {
+            Some(record) => db.put_like(&records::like(did, &uri, &cid, record)).await,
+            None => Ok(()),
+        },
+        PROFILE_COLLECTION if commit.rkey == PROFILE_RKEY => match parse(&uri, record) {
+            Some(record) => db.put_profile(&records::profile(did, record)).await,
+            None => Ok(()),
+        },
+        SERIES_COLLECTION => match parse(&uri, record) {
+            Some(record) => db.put_series(&records::series(did, &uri, &cid, record)).await,
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn parse<T: DeserializeOwned>(uri: &str, record: Value) -> Option<T> {
+    serde_json::from_value(record)
+        .inspect_err(|e| tracing::warn!("skipping {uri}, which doesn't match its lexicon: {e}"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use blogi_db::pg::PostgresDatastore;
+    use blogi_identity::DidResolver;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::jetstream::Operation;
+
+    const DID: &str = "did:plc:alice";
+
+    /// Serves Alice's DID document as both the PLC directory and the AppView
+    /// her handle resolves through.
+    async fn identity_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let body = if String::from_utf8_lossy(&request[..read]).contains("resolveHandle") {
+                    json!({ "did": DID })
+                } else {
+                    json!({
+                        "id": DID,
+                        "alsoKnownAs": ["at://Alice.test"],
+                        "service": [{
+                            "id": "#atproto_pds",
+                            "type": "AtprotoPersonalDataServer",
+                            "serviceEndpoint": "https://pds.test/",
+                        }],
+                    })
+                }
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len(),
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    fn commit(collection: &str, rkey: &str, record: Value) -> Commit {
+        Commit {
+            operation: Operation::Create,
+            collection: collection.to_string(),
+            rkey: rkey.to_string(),
+            record: Some(record),
+            cid: Some("bafyreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy".to_string()),
+        }
+    }
+
+    #[sqlx::test(migrations = "../../../migrations")]
+    async fn profile_commits_make_authors_resolvable(pool: PgPool) {
+        let db = PostgresDatastore(pool).boxed();
+        let url = identity_server().await;
+        let identities = Identities::new(DidResolver::new(reqwest::Client::new(), &url, &url));
+        assert!(db.get_actor("alice.test").await.unwrap().is_none());
+
+        let profile = json!({ "$type": PROFILE_COLLECTION, "displayName": "Alice" });
+        index(db.as_ref(), &identities, DID, commit(PROFILE_COLLECTION, PROFILE_RKEY, profile)).await.unwrap();
+        let entry = json!({ "$type": ENTRY_COLLECTION, "title": "Hello", "content": "World" });
+        index(db.as_ref(), &identities, DID, commit(ENTRY_COLLECTION, "3k", entry)).await.unwrap();
+
+        // Pages look authors up by handle or DID, then their entries by DID.
+        let actor = db.get_actor("alice.test").await.unwrap().expect("the author resolves by handle");
+        assert_eq!(actor.did, DID);
+        assert_eq!(actor.display_name.as_deref(), Some("Alice"));
+        assert_eq!(actor.pds_endpoint.as_deref(), Some("https://pds.test"));
+        assert_eq!(actor.posts_count, 1);
+        assert!(db.get_entry(&actor.did, "3k", None).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,91 @@
+use blogi_db::Datastore;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use url::Url;
+
+use crate::{identities::Identities, index};
+
+/// The name the ingester's position in Jetstream is saved under. Cursors are
+/// timestamps, so they carry over between Jetstream instances.
+pub const SOURCE: &str = "jetstream";
+
+/// An event from Jetstream. Only commits are indexed; identity and account
+/// events are skipped.
+#[derive(Deserialize)]
+struct Event {
+    did: String,
+    time_us: i64,
+    commit: Option<Commit>,
+}
+
+#[derive(Deserialize)]
+pub struct Commit {
+    pub operation: Operation,
+    pub collection: String,
+    pub rkey: String,
+    /// The record and its CID, unless it was deleted.
+    pub record: Option<Value>,
+    pub cid: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Indexes commits to our collections from Jetstream, starting after
+/// `cursor`, until the connection drops.
+///
+/// Jetstream doesn't pass on the signatures over commits, so this trusts the
+/// Jetstream instance to have checked them.
+pub async fn subscribe(
+    url: &str,
+    db: &dyn Datastore,
+    identities: &Identities,
+    cursor: Option<i64>,
+) -> anyhow::Result<()> {
+    let mut url = Url::parse(url)?;
+    {
+        let mut query = url.query_pairs_mut();
+        for collection in index::COLLECTIONS {
+            query.append_pair("wantedCollections", collection);
+        }
+        if let Some(cursor) = cursor {
+            query.append_pair("cursor", &cursor.to_string());
+        }
+    }
+
+    let (mut stream, _) = connect_async(url.as_str()).await?;
+    tracing::info!("subscribed to {url}");
+
+    while let Some(message) = stream.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let event: Event = match serde_json::from_str(&text) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("skipping an event Jetstream sent that doesn't parse: {e}");
+                continue;
+            }
+        };
+        let Some(commit) = event.commit else {
+            continue;
+        };
+
+        // Saving the cursor after every commit means a restart replays at
+        // most the commit that was in flight, which indexes the same way
+        // twice.
+        index::index(db, identities, &event.did, commit).await?;
+        db.put_firehose_cursor(SOURCE, event.time_us).await?;
+    }
+
+    Ok(())
+}
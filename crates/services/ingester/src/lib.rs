@@ -1,6 +1,45 @@
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
+use blogi_identity::DidResolver;
+use identities::Identities;
+use tokio::time::sleep;
+
+mod identities;
+mod index;
+mod jetstream;
+
+/// How long to wait before resubscribing after the firehose drops, doubling
+/// up to `MAX_BACKOFF` while it stays down.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
 
-pub async fn start(_datastore: Box<dyn blogi_db::Datastore>) -> Result<()> {
+pub async fn start(
+    jetstream_url: String,
+    plc_directory_url: &str,
+    appview_url: &str,
+    datastore: Box<dyn blogi_db::Datastore>,
+) -> Result<()> {
     tracing::info!("ingester starting...");
-    Ok(())
+
+    let identities = Identities::new(DidResolver::new(reqwest::Client::new(), plc_directory_url, appview_url));
+
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let subscribed_at = Instant::now();
+        let subscription = async {
+            let cursor = datastore.get_firehose_cursor(jetstream::SOURCE).await?;
+            jetstream::subscribe(&jetstream_url, datastore.as_ref(), &identities, cursor).await
+        };
+        match subscription.await {
+            Ok(()) => tracing::warn!("Jetstream closed the connection"),
+            Err(e) => tracing::warn!("Jetstream subscription failed: {e}"),
+        }
+
+        if subscribed_at.elapsed() > MAX_BACKOFF {
+            backoff = MIN_BACKOFF;
+        }
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
 }
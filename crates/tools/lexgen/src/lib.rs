@@ -0,0 +1,531 @@
+//! Generates the server half of `blogi_lexicons` from the lexicon documents.
+//!
+//! esquema-codegen only generates types and a client, so this fills in the
+//! rest: an `XrpcServer` trait with a method per query and procedure, a
+//! router mounting each of them, and checks for the parameter constraints
+//! serde can't express.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The module declaration esquema-codegen doesn't know to write into
+/// `lib.rs`.
+const MODULE: &str = "#[cfg(feature = \"server\")]\npub mod server;\n";
+
+#[derive(Deserialize)]
+struct Document {
+    id: String,
+    defs: BTreeMap<String, Value>,
+}
+
+#[derive(PartialEq)]
+enum Kind {
+    Query,
+    Procedure,
+}
+
+/// An XRPC method, from the `main` def of a query or procedure lexicon.
+struct Method {
+    nsid: String,
+    kind: Kind,
+    description: Option<String>,
+    params: Vec<Param>,
+    input: bool,
+    output: bool,
+}
+
+struct Param {
+    name: String,
+    required: bool,
+    checks: Vec<Check>,
+}
+
+enum Check {
+    AtUri,
+    Length(Option<u64>, Option<u64>),
+    Graphemes(Option<u64>, Option<u64>),
+    Enum(Vec<String>),
+}
+
+impl Method {
+    /// The module esquema-codegen generates the method's types in.
+    fn module(&self) -> String {
+        let segments: Vec<_> = self.nsid.split('.').map(snake_case).collect();
+        format!("crate::{}", segments.join("::"))
+    }
+
+    /// The trait method's name: the method's name within its group, like
+    /// `blog_get_likes`.
+    fn name(&self) -> String {
+        let segments: Vec<_> = self.nsid.rsplit('.').take(2).map(snake_case).collect();
+        format!("{}_{}", segments[1], segments[0])
+    }
+}
+
+/// Generates `server.rs` from every lexicon under `lexicons`.
+pub fn generate(lexicons: &Path) -> Result<String> {
+    let mut methods = Vec::new();
+    read_methods(lexicons, &mut methods)?;
+    methods.sort_by(|a, b| a.nsid.cmp(&b.nsid));
+
+    let mut names = BTreeSet::new();
+    for method in &methods {
+        if !names.insert(method.name()) {
+            bail!("{} has the same method name as another lexicon", method.nsid);
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("// @generated - This file is generated by blogi-lexgen (`just lexgen-server`). DO NOT EDIT.\n");
+    out.push_str("//!Traits for an ATP server, routes all HTTP APIs of XRPC.\n");
+    write_nsids(&mut out, &methods);
+    write_trait(&mut out, &methods);
+    write_router(&mut out, &methods);
+    write_extractors(&mut out, &methods);
+    write_validate(&mut out, &methods);
+    Ok(out)
+}
+
+/// Adds the `server` module to esquema-codegen's `lib.rs`, if it isn't
+/// there already.
+pub fn declare_module(lib: &str) -> String {
+    if lib.contains(MODULE) {
+        return lib.to_string();
+    }
+    match lib.find("pub mod client;\n") {
+        Some(at) => {
+            let at = at + "pub mod client;\n".len();
+            format!("{}{MODULE}{}", &lib[..at], &lib[at..])
+        }
+        None => format!("{lib}{MODULE}"),
+    }
+}
+
+fn read_methods(dir: &Path, methods: &mut Vec<Method>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_methods(&path, methods)?;
+        } else if path.extension().is_some_and(|extension| extension == "json") {
+            let json = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            let document: Document =
+                serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+            if let Some(method) = method(&document)? {
+                methods.push(method);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn method(document: &Document) -> Result<Option<Method>> {
+    let Some(main) = document.defs.get("main") else {
+        return Ok(None);
+    };
+    let kind = match main["type"].as_str() {
+        Some("query") => Kind::Query,
+        Some("procedure") => Kind::Procedure,
+        _ => return Ok(None),
+    };
+
+    let required: BTreeSet<_> = main["parameters"]["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let params = main["parameters"]["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, schema)| Param {
+            name: name.clone(),
+            required: required.contains(name.as_str()),
+            checks: checks(schema),
+        })
+        .collect();
+
+    let input = main["input"]["schema"].is_object();
+    if input && main["input"]["encoding"] != "application/json" {
+        bail!("{} takes a non-JSON input, which isn't supported", document.id);
+    }
+
+    Ok(Some(Method {
+        nsid: document.id.clone(),
+        kind,
+        description: main["description"].as_str().map(str::to_string),
+        params,
+        input,
+        output: main["output"]["schema"].is_object(),
+    }))
+}
+
+/// The constraints on a parameter that its generated type doesn't enforce.
+/// Formats other than AT URIs get their own types, and integers are
+/// generated as bounded types.
+fn checks(schema: &Value) -> Vec<Check> {
+    if schema["type"] != "string" {
+        return Vec::new();
+    }
+
+    let mut checks = Vec::new();
+    if schema["format"] == "at-uri" {
+        checks.push(Check::AtUri);
+    }
+    let (min, max) = (schema["minLength"].as_u64(), schema["maxLength"].as_u64());
+    if min.is_some() || max.is_some() {
+        checks.push(Check::Length(min, max));
+    }
+    let (min, max) = (schema["minGraphemes"].as_u64(), schema["maxGraphemes"].as_u64());
+    if min.is_some() || max.is_some() {
+        checks.push(Check::Graphemes(min, max));
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        checks.push(Check::Enum(values.iter().filter_map(Value::as_str).map(str::to_string).collect()));
+    }
+    checks
+}
+
+fn write_nsids(out: &mut String, methods: &[Method]) {
+    out.push_str("///Every XRPC method in the lexicons, all of which `router` mounts.\n");
+    out.push_str("pub const NSIDS: &[&str] = &[\n");
+    for method in methods {
+        writeln!(out, "    {}::NSID,", method.module()).unwrap();
+    }
+    out.push_str("];\n");
+}
+
+fn write_trait(out: &mut String, methods: &[Method]) {
+    out.push_str(
+        "///Server trait for the ATP service. Methods that aren't implemented respond with `MethodNotImplemented`.
+#[async_trait::async_trait]
+#[allow(unused_variables)]
+pub trait XrpcServer: Clone + Send + Sync + 'static {
+    ///Extracted from each request and passed to its method, such as the authenticated caller.
+    type Context: axum::extract::FromRequestParts<Self, Rejection = Self::Error> + Send;
+    type Error: axum::response::IntoResponse + Send;
+    fn invalid_request(message: String) -> Self::Error;
+    fn method_not_implemented(nsid: &'static str) -> Self::Error;
+",
+    );
+    for method in methods {
+        let module = method.module();
+        if let Some(description) = &method.description {
+            writeln!(out, "    ///{description}").unwrap();
+        }
+        writeln!(out, "    async fn {}(", method.name()).unwrap();
+        out.push_str("        &self,\n");
+        out.push_str("        context: Self::Context,\n");
+        if !method.params.is_empty() {
+            writeln!(out, "        params: {module}::Parameters,").unwrap();
+        }
+        if method.input {
+            writeln!(out, "        input: {module}::Input,").unwrap();
+        }
+        let output = if method.output { format!("{module}::Output") } else { "()".to_string() };
+        writeln!(out, "    ) -> core::result::Result<{output}, Self::Error> {{").unwrap();
+        writeln!(out, "        Err(Self::method_not_implemented({module}::NSID))").unwrap();
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+}
+
+fn write_router(out: &mut String, methods: &[Method]) {
+    out.push_str("///Builds a router that mounts every XRPC method at `/xrpc/<nsid>`.\n");
+    out.push_str("pub fn router<S: XrpcServer>() -> axum::Router<S> {\n");
+    out.push_str("    axum::Router::new()\n");
+    for method in methods {
+        let module = method.module();
+        let routing = match method.kind {
+            Kind::Query => "get",
+            Kind::Procedure => "post",
+        };
+
+        let mut extractors = vec![
+            "axum::extract::State(server): axum::extract::State<S>".to_string(),
+            "context: S::Context".to_string(),
+        ];
+        let mut args = vec!["context"];
+        if !method.params.is_empty() {
+            extractors.push(format!("XrpcParams(params): XrpcParams<{module}::Parameters>"));
+            args.push("params");
+        }
+        if method.input {
+            extractors.push(format!("XrpcInput(input): XrpcInput<{module}::Input>"));
+            args.push("input");
+        }
+        let respond = if method.output { ".map(axum::Json)" } else { "" };
+
+        out.push_str("        .route(\n");
+        writeln!(out, "            \"/xrpc/{}\",", method.nsid).unwrap();
+        writeln!(out, "            axum::routing::{routing}(").unwrap();
+        writeln!(out, "                |{}| async move {{", extractors.join(",\n                 ")).unwrap();
+        writeln!(out, "                    server.{}({}).await{respond}", method.name(), args.join(", ")).unwrap();
+        out.push_str("                },\n");
+        out.push_str("            ),\n");
+        out.push_str("        )\n");
+    }
+    out.push_str("}\n");
+}
+
+fn write_extractors(out: &mut String, methods: &[Method]) {
+    if methods.iter().any(|method| !method.params.is_empty()) {
+        out.push_str(
+            "///Query parameters of an XRPC method, checked against its lexicon.
+pub struct XrpcParams<T>(pub T);
+impl<S, T> axum::extract::FromRequestParts<S> for XrpcParams<atrium_api::types::Object<T>>
+where
+    S: XrpcServer,
+    T: serde::de::DeserializeOwned + Validate,
+{
+    type Rejection = S::Error;
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> core::result::Result<Self, Self::Rejection> {
+        //`Object` flattens its data, which hides the integers in a query string from serde.
+        let params: T = serde_html_form::from_str(parts.uri.query().unwrap_or_default())
+            .map_err(|e| S::invalid_request(e.to_string()))?;
+        params.validate().map_err(S::invalid_request)?;
+        Ok(XrpcParams(params.into()))
+    }
+}
+",
+        );
+    }
+    if methods.iter().any(|method| method.input) {
+        out.push_str(
+            "///The JSON input of an XRPC procedure.
+pub struct XrpcInput<T>(pub T);
+impl<S, T> axum::extract::FromRequest<S> for XrpcInput<T>
+where
+    S: XrpcServer,
+    T: serde::de::DeserializeOwned,
+{
+    type Rejection = S::Error;
+    async fn from_request(
+        request: axum::extract::Request,
+        state: &S,
+    ) -> core::result::Result<Self, Self::Rejection> {
+        let axum::Json(input) = <axum::Json<T> as axum::extract::FromRequest<S>>::from_request(request, state)
+            .await
+            .map_err(|e| S::invalid_request(e.body_text()))?;
+        Ok(XrpcInput(input))
+    }
+}
+",
+        );
+    }
+}
+
+fn write_validate(out: &mut String, methods: &[Method]) {
+    let methods: Vec<_> = methods.iter().filter(|method| !method.params.is_empty()).collect();
+    if methods.is_empty() {
+        return;
+    }
+
+    out.push_str(
+        "///Checks the constraints on parameters that their types don't enforce.
+pub trait Validate {
+    fn validate(&self) -> core::result::Result<(), String> {
+        Ok(())
+    }
+}
+",
+    );
+
+    let checks: Vec<_> = methods.iter().flat_map(|method| &method.params).flat_map(|param| &param.checks).collect();
+    if checks.iter().any(|check| matches!(check, Check::AtUri)) {
+        out.push_str(
+            "///Checks `value` is an AT URI: an account, optionally followed by a collection and a record key.
+fn validate_at_uri(name: &str, value: &str) -> core::result::Result<(), String> {
+    let invalid = || format!(\"{name} must be an AT URI\");
+    let mut segments = value.strip_prefix(\"at://\").ok_or_else(invalid)?.split('/');
+    segments
+        .next()
+        .unwrap_or_default()
+        .parse::<atrium_api::types::string::AtIdentifier>()
+        .map_err(|_| invalid())?;
+    if let Some(collection) = segments.next() {
+        atrium_api::types::string::Nsid::new(collection.to_string()).map_err(|_| invalid())?;
+    }
+    if let Some(rkey) = segments.next() {
+        atrium_api::types::string::RecordKey::new(rkey.to_string()).map_err(|_| invalid())?;
+    }
+    match segments.next() {
+        Some(_) => Err(invalid()),
+        None => Ok(()),
+    }
+}
+",
+        );
+    }
+    if checks.iter().any(|check| matches!(check, Check::Length(..))) {
+        out.push_str(
+            "///Checks the length of `value` in bytes, as `minLength` and `maxLength` count it.
+fn validate_length(name: &str, value: &str, min: usize, max: usize) -> core::result::Result<(), String> {
+    if value.len() < min {
+        return Err(format!(\"{name} must be at least {min} bytes long\"));
+    }
+    if value.len() > max {
+        return Err(format!(\"{name} must be at most {max} bytes long\"));
+    }
+    Ok(())
+}
+",
+        );
+    }
+    if checks.iter().any(|check| matches!(check, Check::Graphemes(..))) {
+        out.push_str(
+            "///Checks the length of `value` in graphemes, as `minGraphemes` and `maxGraphemes` count it.
+fn validate_graphemes(name: &str, value: &str, min: usize, max: usize) -> core::result::Result<(), String> {
+    let graphemes = unicode_segmentation::UnicodeSegmentation::graphemes(value, true).count();
+    if graphemes < min {
+        return Err(format!(\"{name} must be at least {min} characters long\"));
+    }
+    if graphemes > max {
+        return Err(format!(\"{name} must be at most {max} characters long\"));
+    }
+    Ok(())
+}
+",
+        );
+    }
+    if checks.iter().any(|check| matches!(check, Check::Enum(_))) {
+        out.push_str(
+            "///Checks `value` is one of the values a lexicon allows.
+fn validate_enum(name: &str, value: &str, values: &[&str]) -> core::result::Result<(), String> {
+    if values.contains(&value) {
+        return Ok(());
+    }
+    Err(format!(\"{name} must be one of: {}\", values.join(\", \")))
+}
+",
+        );
+    }
+
+    for method in methods {
+        let module = method.module();
+        if method.params.iter().all(|param| param.checks.is_empty()) {
+            writeln!(out, "impl Validate for {module}::ParametersData {{}}").unwrap();
+            continue;
+        }
+
+        writeln!(out, "impl Validate for {module}::ParametersData {{").unwrap();
+        out.push_str("    fn validate(&self) -> core::result::Result<(), String> {\n");
+        for param in method.params.iter().filter(|param| !param.checks.is_empty()) {
+            let field = field_name(&param.name);
+            let (indent, value) = if param.required {
+                ("        ", format!("&self.{field}"))
+            } else {
+                writeln!(out, "        if let Some({field}) = &self.{field} {{").unwrap();
+                ("            ", field.clone())
+            };
+            for check in &param.checks {
+                let name = &param.name;
+                let call = match check {
+                    Check::AtUri => format!("validate_at_uri(\"{name}\", {value})"),
+                    Check::Length(min, max) => {
+                        format!("validate_length(\"{name}\", {value}, {})", bounds(min, max))
+                    }
+                    Check::Graphemes(min, max) => {
+                        format!("validate_graphemes(\"{name}\", {value}, {})", bounds(min, max))
+                    }
+                    Check::Enum(values) => {
+                        let values: Vec<_> = values.iter().map(|value| format!("{value:?}")).collect();
+                        format!("validate_enum(\"{name}\", {value}, &[{}])", values.join(", "))
+                    }
+                };
+                writeln!(out, "{indent}{call}?;").unwrap();
+            }
+            if !param.required {
+                out.push_str("        }\n");
+            }
+        }
+        out.push_str("        Ok(())\n");
+        out.push_str("    }\n");
+        out.push_str("}\n");
+    }
+}
+
+/// The arguments for a `min` and `max` that either may leave unbounded.
+fn bounds(min: &Option<u64>, max: &Option<u64>) -> String {
+    let min = min.map_or("0".to_string(), |min| min.to_string());
+    let max = max.map_or("usize::MAX".to_string(), |max| max.to_string());
+    format!("{min}, {max}")
+}
+
+/// Converts a lexicon name to snake case, the way esquema-codegen does for
+/// modules and fields.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn field_name(name: &str) -> String {
+    let field = snake_case(name);
+    match field.as_str() {
+        "type" | "ref" | "self" | "match" | "move" | "use" | "where" | "async" | "await" | "dyn" | "impl" => {
+            format!("r#{field}")
+        }
+        _ => field,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn workspace() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../..")
+    }
+
+    #[test]
+    fn generated_server_is_up_to_date() {
+        let generated = generate(&workspace().join("lexicons")).unwrap();
+        let committed = fs::read_to_string(workspace().join("crates/libs/lexicons/src/server.rs")).unwrap();
+        assert!(generated == committed, "server.rs is out of date with the lexicons; run `just lexgen-server`");
+    }
+
+    #[test]
+    fn declares_the_module_once() {
+        let lib = "pub mod record;\npub mod client;\npub mod moe;\n";
+        let declared = declare_module(lib);
+        assert_eq!(declared, format!("pub mod record;\npub mod client;\n{MODULE}pub mod moe;\n"));
+        assert_eq!(declare_module(&declared), declared);
+    }
+
+    #[test]
+    fn names_methods_after_their_group() {
+        let method = Method {
+            nsid: "moe.hayden.blogi.blog.getEntriesByTag".to_string(),
+            kind: Kind::Query,
+            description: None,
+            params: Vec::new(),
+            input: false,
+            output: true,
+        };
+        assert_eq!(method.name(), "blog_get_entries_by_tag");
+        assert_eq!(method.module(), "crate::moe::hayden::blogi::blog::get_entries_by_tag");
+    }
+}
@@ -0,0 +1,28 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+/// Generates `blogi_lexicons::server` from the lexicon documents
+#[derive(Parser)]
+#[clap(name = "blogi-lexgen")]
+struct Cli {
+    /// The directory of lexicon documents to read
+    #[arg(long, short, default_value = "lexicons")]
+    lexicons: PathBuf,
+
+    /// The generated crate's source directory, which esquema-codegen wrote to
+    #[arg(long, short, default_value = "crates/libs/lexicons/src")]
+    out: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    fs::write(cli.out.join("server.rs"), blogi_lexgen::generate(&cli.lexicons)?).context("writing server.rs")?;
+
+    let lib = cli.out.join("lib.rs");
+    let declared = blogi_lexgen::declare_module(&fs::read_to_string(&lib).context("reading lib.rs")?);
+    fs::write(&lib, declared).context("writing lib.rs")?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+CREATE TABLE actor (
+    did TEXT PRIMARY KEY,
+    handle TEXT NOT NULL,
+    pds_endpoint TEXT,
+    display_name TEXT,
+    description TEXT,
+    avatar_cid TEXT,
+    banner_cid TEXT,
+    created_at TIMESTAMPTZ,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX actor_handle_idx ON actor (handle);
+
+CREATE TABLE entry (
+    uri TEXT PRIMARY KEY,
+    cid TEXT NOT NULL,
+    did TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    status TEXT,
+    visibility TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (did, rkey)
+);
+
+CREATE INDEX entry_did_created_at_idx ON entry (did, created_at DESC);
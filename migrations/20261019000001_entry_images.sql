@@ -0,0 +1,13 @@
+CREATE TABLE entry_image (
+    entry_uri TEXT NOT NULL REFERENCES entry (uri) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    cid TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    alt TEXT NOT NULL,
+    aspect_width BIGINT,
+    aspect_height BIGINT,
+    PRIMARY KEY (entry_uri, position)
+);
+
+CREATE INDEX entry_image_cid_idx ON entry_image (cid);
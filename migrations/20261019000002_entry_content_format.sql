@@ -0,0 +1,2 @@
+-- NULL means the record didn't set a format, which is treated as Markdown.
+ALTER TABLE entry ADD COLUMN content_format TEXT;
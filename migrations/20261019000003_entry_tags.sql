@@ -0,0 +1,3 @@
+ALTER TABLE entry ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX entry_tags_idx ON entry USING GIN (tags);
@@ -0,0 +1,24 @@
+CREATE TABLE series (
+    uri TEXT PRIMARY KEY,
+    cid TEXT NOT NULL,
+    did TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (did, rkey)
+);
+
+-- Entries are referenced by strongRef and may not be indexed (yet), so there's
+-- no foreign key to entry here.
+CREATE TABLE series_entry (
+    series_uri TEXT NOT NULL REFERENCES series (uri) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    entry_uri TEXT NOT NULL,
+    entry_cid TEXT NOT NULL,
+    PRIMARY KEY (series_uri, position)
+);
+
+CREATE INDEX series_entry_entry_uri_idx ON series_entry (entry_uri);
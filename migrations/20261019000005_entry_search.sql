@@ -0,0 +1,8 @@
+-- Titles rank above body text. Both are stemmed as English, so "running"
+-- matches "run".
+ALTER TABLE entry ADD COLUMN search TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', title), 'A') ||
+    setweight(to_tsvector('english', content), 'B')
+) STORED;
+
+CREATE INDEX entry_search_idx ON entry USING GIN (search);
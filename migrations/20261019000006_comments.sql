@@ -0,0 +1,17 @@
+-- Comments point at entries by strongRef and may arrive before the entry is
+-- indexed, so there's no foreign key to entry here.
+CREATE TABLE comment (
+    uri TEXT PRIMARY KEY,
+    cid TEXT NOT NULL,
+    did TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    post_uri TEXT NOT NULL,
+    post_cid TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (did, rkey)
+);
+
+CREATE INDEX comment_post_uri_idx ON comment (post_uri, created_at);
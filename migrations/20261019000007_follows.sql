@@ -0,0 +1,13 @@
+CREATE TABLE follow (
+    uri TEXT PRIMARY KEY,
+    cid TEXT NOT NULL,
+    did TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (did, rkey)
+);
+
+CREATE INDEX follow_did_idx ON follow (did, subject);
+CREATE INDEX follow_subject_idx ON follow (subject, did);
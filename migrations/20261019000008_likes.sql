@@ -0,0 +1,16 @@
+-- "like" is a reserved word, hence the name. Subjects are strongRefs and may
+-- not be indexed, so there's no foreign key to entry.
+CREATE TABLE entry_like (
+    uri TEXT PRIMARY KEY,
+    cid TEXT NOT NULL,
+    did TEXT NOT NULL,
+    rkey TEXT NOT NULL,
+    subject_uri TEXT NOT NULL,
+    subject_cid TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (did, rkey)
+);
+
+CREATE INDEX entry_like_subject_idx ON entry_like (subject_uri, created_at DESC);
+CREATE INDEX entry_like_did_idx ON entry_like (did, subject_uri);
@@ -0,0 +1,2 @@
+ALTER TABLE entry ADD COLUMN bsky_post_uri TEXT;
+ALTER TABLE entry ADD COLUMN bsky_post_cid TEXT;
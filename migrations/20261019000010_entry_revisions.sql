@@ -0,0 +1,15 @@
+-- Versions of an entry that have since been replaced, copied out of entry
+-- whenever it's re-indexed with a new CID.
+CREATE TABLE entry_revision (
+    entry_uri TEXT NOT NULL REFERENCES entry (uri) ON DELETE CASCADE,
+    cid TEXT NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    content_format TEXT,
+    updated_at TIMESTAMPTZ,
+    indexed_at TIMESTAMPTZ NOT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (entry_uri, cid)
+);
+
+CREATE INDEX entry_revision_replaced_at_idx ON entry_revision (entry_uri, replaced_at DESC);
@@ -0,0 +1,11 @@
+-- Entries with a publish_at in the future stay hidden until then. Feeds order
+-- by when an entry went live rather than when it was written.
+ALTER TABLE entry ADD COLUMN publish_at TIMESTAMPTZ;
+ALTER TABLE entry ADD COLUMN published_at TIMESTAMPTZ
+    GENERATED ALWAYS AS (coalesce(publish_at, created_at)) STORED;
+
+-- Set by the scheduler once it has announced a scheduled entry going live.
+ALTER TABLE entry ADD COLUMN announced_at TIMESTAMPTZ;
+
+CREATE INDEX entry_published_at_idx ON entry (published_at DESC);
+CREATE INDEX entry_scheduled_idx ON entry (publish_at) WHERE announced_at IS NULL;
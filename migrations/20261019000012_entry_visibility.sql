@@ -0,0 +1,18 @@
+-- The one place that decides who can see an entry. `viewer` is the DID of the
+-- requesting account, or NULL for anonymous requests.
+
+-- Whether an entry can be read at all. Authors always see their own entries;
+-- everyone else sees live ones once their publish time has passed.
+CREATE FUNCTION entry_visible(e entry, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT coalesce(e.did = viewer, false)
+        OR (coalesce(e.status, 'live') = 'live' AND (e.publish_at IS NULL OR e.publish_at <= now()))
+$$;
+
+-- Whether an entry shows up in listings, feeds and search. Unlisted entries
+-- can only be reached by link, except by their author.
+CREATE FUNCTION entry_listed(e entry, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT entry_visible(e, viewer)
+        AND (coalesce(e.did = viewer, false) OR coalesce(e.visibility, 'public') = 'public')
+$$;
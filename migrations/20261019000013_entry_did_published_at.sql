@@ -0,0 +1,3 @@
+-- Per-author listings are ordered by publish time like every other feed.
+DROP INDEX entry_did_created_at_idx;
+CREATE INDEX entry_did_published_at_idx ON entry (did, published_at DESC);
@@ -0,0 +1,7 @@
+-- Lists page by (sort key, uri) so items sharing a timestamp aren't skipped.
+DROP INDEX entry_published_at_idx;
+CREATE INDEX entry_published_at_idx ON entry (published_at DESC, uri DESC);
+DROP INDEX entry_did_published_at_idx;
+CREATE INDEX entry_did_published_at_idx ON entry (did, published_at DESC, uri DESC);
+DROP INDEX entry_like_subject_idx;
+CREATE INDEX entry_like_subject_idx ON entry_like (subject_uri, created_at DESC, uri DESC);
@@ -0,0 +1,7 @@
+-- How far the ingester has read each firehose it subscribes to, so a restart
+-- picks up where it left off instead of missing or replaying records.
+CREATE TABLE firehose_cursor (
+    source TEXT PRIMARY KEY,
+    cursor BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
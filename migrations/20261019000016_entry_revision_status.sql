@@ -0,0 +1,13 @@
+-- Revisions remember whether the version they copied was public, so text
+-- that was only ever a draft, or replaced before its publish time, stays
+-- with the author once the entry goes live. Older revisions count as live.
+ALTER TABLE entry_revision ADD COLUMN status TEXT;
+ALTER TABLE entry_revision ADD COLUMN publish_at TIMESTAMPTZ;
+
+-- Whether a revision of a visible entry can be read. Authors see all of them;
+-- everyone else only sees versions that were live while they were current.
+CREATE FUNCTION entry_revision_visible(r entry_revision, e entry, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT coalesce(e.did = viewer, false)
+        OR (coalesce(r.status, 'live') = 'live' AND (r.publish_at IS NULL OR r.publish_at <= r.replaced_at))
+$$;
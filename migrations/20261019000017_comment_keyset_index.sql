@@ -0,0 +1,3 @@
+-- Comments page by (created_at, uri), oldest first.
+DROP INDEX comment_post_uri_idx;
+CREATE INDEX comment_post_uri_idx ON comment (post_uri, created_at, uri);
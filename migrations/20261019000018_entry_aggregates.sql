@@ -0,0 +1,28 @@
+-- What every read of an entry computes alongside its row. They take the URI
+-- rather than the row so ranked subqueries can call them too.
+
+-- The entry's images, in order, as the JSON `Entry::images` decodes.
+CREATE FUNCTION entry_images(entry_uri TEXT) RETURNS JSON
+LANGUAGE sql STABLE AS $$
+    SELECT coalesce(json_agg(json_build_object(
+        'cid', cid,
+        'mime_type', mime_type,
+        'size', size,
+        'alt', alt,
+        'aspect_width', aspect_width,
+        'aspect_height', aspect_height
+    ) ORDER BY position), '[]')
+    FROM entry_image
+    WHERE entry_image.entry_uri = entry_images.entry_uri
+$$;
+
+CREATE FUNCTION entry_comment_count(entry_uri TEXT) RETURNS BIGINT
+LANGUAGE sql STABLE AS $$
+    SELECT count(*) FROM comment WHERE post_uri = entry_uri
+$$;
+
+-- Accounts that liked the entry, however many like records each has.
+CREATE FUNCTION entry_like_count(entry_uri TEXT) RETURNS BIGINT
+LANGUAGE sql STABLE AS $$
+    SELECT count(DISTINCT did) FROM entry_like WHERE subject_uri = entry_uri
+$$;
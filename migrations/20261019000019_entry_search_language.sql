@@ -0,0 +1,56 @@
+-- Entries are stemmed in the language they're written in, rather than all as
+-- English. Entries in a language Postgres can't stem, or that don't say, are
+-- only split into words.
+ALTER TABLE entry ADD COLUMN langs TEXT[] NOT NULL DEFAULT '{}';
+
+-- Picks the text search configuration for an entry's first language, going
+-- by its primary subtag so "en-GB" stems as English.
+CREATE FUNCTION entry_search_config(langs TEXT[]) RETURNS REGCONFIG
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT CASE lower(split_part(langs[1], '-', 1))
+        WHEN 'ar' THEN 'arabic'::REGCONFIG
+        WHEN 'ca' THEN 'catalan'
+        WHEN 'da' THEN 'danish'
+        WHEN 'de' THEN 'german'
+        WHEN 'el' THEN 'greek'
+        WHEN 'en' THEN 'english'
+        WHEN 'es' THEN 'spanish'
+        WHEN 'eu' THEN 'basque'
+        WHEN 'fi' THEN 'finnish'
+        WHEN 'fr' THEN 'french'
+        WHEN 'ga' THEN 'irish'
+        WHEN 'hi' THEN 'hindi'
+        WHEN 'hu' THEN 'hungarian'
+        WHEN 'hy' THEN 'armenian'
+        WHEN 'id' THEN 'indonesian'
+        WHEN 'it' THEN 'italian'
+        WHEN 'lt' THEN 'lithuanian'
+        WHEN 'nb' THEN 'norwegian'
+        WHEN 'ne' THEN 'nepali'
+        WHEN 'nl' THEN 'dutch'
+        WHEN 'nn' THEN 'norwegian'
+        WHEN 'no' THEN 'norwegian'
+        WHEN 'pt' THEN 'portuguese'
+        WHEN 'ro' THEN 'romanian'
+        WHEN 'ru' THEN 'russian'
+        WHEN 'sr' THEN 'serbian'
+        WHEN 'sv' THEN 'swedish'
+        WHEN 'ta' THEN 'tamil'
+        WHEN 'tr' THEN 'turkish'
+        WHEN 'yi' THEN 'yiddish'
+        ELSE 'simple'
+    END
+$$;
+
+-- Searches parse the query with each entry's own configuration, so its
+-- words are stemmed the same way as the entry's.
+ALTER TABLE entry ADD COLUMN search_config REGCONFIG
+    GENERATED ALWAYS AS (entry_search_config(langs)) STORED;
+
+ALTER TABLE entry DROP COLUMN search;
+ALTER TABLE entry ADD COLUMN search TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector(entry_search_config(langs), title), 'A') ||
+    setweight(to_tsvector(entry_search_config(langs), content), 'B')
+) STORED;
+
+CREATE INDEX entry_search_idx ON entry USING GIN (search);